@@ -0,0 +1,413 @@
+use std::fs;
+use std::io;
+use std::ops::Add;
+use std::path::Path;
+use serde::Deserialize;
+use crate::{BuffUptimes, DamagingCondition, PlayerStats, Sigil, Stat, Trait, WeaponSet, WeaponType};
+use crate::equipment::{Equipment, EquipmentItem, FoodBonus, LEVEL_80_BASE_ATTRIBUTES};
+use crate::gamedata::itemstats::{self, Rarity, Slot};
+use crate::gamedata::runes::{self, RuneEffect, RuneSet};
+use crate::gamedata::traits::{self, SpecializationLine, TraitModifier};
+use crate::modifiers::{DamageModifier, DamageTypes, ModifierBucket, ModifierRequirement, ModifierScaling};
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, Eq, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Attributes {
+    pub power: u32,
+    pub precision: u32,
+    pub ferocity: u32,
+    pub condition_damage: u32,
+    pub expertise: u32,
+    pub concentration: u32,
+}
+
+impl Add for Attributes {
+    type Output = Attributes;
+
+    fn add(self, other: Attributes) -> Attributes {
+        Attributes {
+            power: self.power + other.power,
+            precision: self.precision + other.precision,
+            ferocity: self.ferocity + other.ferocity,
+            condition_damage: self.condition_damage + other.condition_damage,
+            expertise: self.expertise + other.expertise,
+            concentration: self.concentration + other.concentration,
+        }
+    }
+}
+
+impl Attributes {
+    pub const ZERO: Attributes = Attributes {
+        power: 0,
+        precision: 0,
+        ferocity: 0,
+        condition_damage: 0,
+        expertise: 0,
+        concentration: 0,
+    };
+
+    fn scaled(self, factor: u32) -> Attributes {
+        Attributes {
+            power: self.power * factor,
+            precision: self.precision * factor,
+            ferocity: self.ferocity * factor,
+            condition_damage: self.condition_damage * factor,
+            expertise: self.expertise * factor,
+            concentration: self.concentration * factor,
+        }
+    }
+
+    pub fn get(&self, stat: Stat) -> u32 {
+        match stat {
+            Stat::Power => self.power,
+            Stat::Precision => self.precision,
+            Stat::Ferocity => self.ferocity,
+            Stat::ConditionDamage => self.condition_damage,
+            Stat::Expertise => self.expertise,
+        }
+    }
+
+    pub fn get_mut(&mut self, stat: Stat) -> &mut u32 {
+        match stat {
+            Stat::Power => &mut self.power,
+            Stat::Precision => &mut self.precision,
+            Stat::Ferocity => &mut self.ferocity,
+            Stat::ConditionDamage => &mut self.condition_damage,
+            Stat::Expertise => &mut self.expertise,
+        }
+    }
+}
+
+pub const INFUSION_ATTRIBUTES: u32 = 5;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize)]
+pub enum Weapon {
+    Axe,
+    Dagger,
+    Mace,
+    Pistol,
+    Scepter,
+    Sword,
+    Focus,
+    Shield,
+    Torch,
+    Warhorn,
+    Greatsword,
+    Hammer,
+    Longbow,
+    Rifle,
+    Shortbow,
+    Staff,
+}
+
+impl Weapon {
+    /// Average of the ascended weapon strength range.
+    pub fn average_strength(&self) -> f64 {
+        match self {
+            Weapon::Axe => 952.5,
+            Weapon::Dagger => 1000.,
+            Weapon::Mace => 1000.,
+            Weapon::Pistol => 1000.,
+            Weapon::Scepter => 1000.,
+            Weapon::Sword => 1000.,
+            Weapon::Focus => 900.,
+            Weapon::Shield => 900.,
+            Weapon::Torch => 900.,
+            Weapon::Warhorn => 900.,
+            Weapon::Greatsword => 1100.,
+            Weapon::Hammer => 1100.,
+            Weapon::Longbow => 1050.,
+            Weapon::Rifle => 1150.,
+            Weapon::Shortbow => 1000.,
+            Weapon::Staff => 1100.,
+        }
+    }
+
+    pub fn is_two_handed(&self) -> bool {
+        matches!(self, Weapon::Greatsword | Weapon::Hammer | Weapon::Longbow | Weapon::Rifle | Weapon::Shortbow | Weapon::Staff)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct WeaponSetDescription {
+    /// Either a single two-handed weapon or a main hand and an off hand weapon.
+    pub weapons: Vec<Weapon>,
+    /// Prefix of all weapons in the set.
+    pub prefix: String,
+    pub rarity: Rarity,
+    pub sigils: [Sigil; 2],
+}
+
+impl WeaponSetDescription {
+    pub fn weapon_type(&self) -> WeaponType {
+        self.checked_weapon_type().unwrap_or_else(|| panic!("Invalid weapon set {:?}", self.weapons))
+    }
+
+    /// None unless the set is a single two-handed weapon or two one-handed weapons.
+    fn checked_weapon_type(&self) -> Option<WeaponType> {
+        match self.weapons.as_slice() {
+            [weapon] if weapon.is_two_handed() => Some(WeaponType::TwoHanded),
+            [main_hand, off_hand] if !main_hand.is_two_handed() && !off_hand.is_two_handed() => Some(WeaponType::DualWield),
+            _ => None,
+        }
+    }
+
+    /// Slot and item of the weapons, both weapons of a dual wield set share the same item.
+    pub fn item(&self) -> (Slot, EquipmentItem) {
+        let item = EquipmentItem { prefix: self.prefix.clone(), rarity: self.rarity };
+        match self.weapon_type() {
+            WeaponType::TwoHanded => (Slot::TwoHandedWeapon, item),
+            WeaponType::DualWield => (Slot::OneHandedWeapon, item),
+        }
+    }
+
+    pub fn attributes(&self) -> Attributes {
+        match self.item() {
+            (slot @ Slot::TwoHandedWeapon, item) => item.attributes(slot),
+            (slot, item) => item.attributes(slot) + item.attributes(slot),
+        }
+    }
+}
+
+/// A bonus that applies to a single condition.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ConditionBonus {
+    pub condition: DamagingCondition,
+    pub value: f64,
+}
+
+/// Extra condition duration while the player has a buff.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct BuffConditionDuration {
+    pub condition: DamagingCondition,
+    /// Skill id of the buff
+    pub buff: u32,
+    pub extra_duration: f64,
+}
+
+/// A specialization line and the traits selected in it.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Specialization {
+    pub line: SpecializationLine,
+    #[serde(default)]
+    pub traits: Vec<Trait>,
+}
+
+/// Build description as stored in a TOML file.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct BuildFile {
+    /// Armor and trinkets.
+    pub equipment: Equipment,
+    /// Name of the rune set, for example Nightmare
+    pub runes: String,
+    /// Amount of infusions for each attribute.
+    #[serde(default)]
+    pub infusions: Attributes,
+    #[serde(default)]
+    pub food: Option<FoodBonus>,
+    /// Attributes from sources that are not modelled.
+    #[serde(default)]
+    pub extra_attributes: Attributes,
+    /// Land weapon sets 1 and 2.
+    pub weapon_sets: [WeaponSetDescription; 2],
+    /// Weapon set the player starts the fight with.
+    pub starting_weapon_set: WeaponSet,
+    /// Up to three specialization lines with their selected traits.
+    #[serde(default)]
+    pub specializations: Vec<Specialization>,
+    /// Modifiers that are not covered by runes and traits.
+    #[serde(default)]
+    pub extra_critical_chance: f64,
+    #[serde(default)]
+    pub extra_condition_durations_all: f64,
+    #[serde(default)]
+    pub extra_condition_durations: Vec<ConditionBonus>,
+    #[serde(default)]
+    pub extra_condition_durations_under_buff: Vec<BuffConditionDuration>,
+    #[serde(default)]
+    pub extra_condition_damages: Vec<ConditionBonus>,
+}
+
+impl BuildFile {
+    pub fn from_file<P: AsRef<Path>>(filename: P) -> io::Result<Self> {
+        let contents = fs::read_to_string(&filename)?;
+        let build: BuildFile = toml::from_str(&contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        for weapon_set in &build.weapon_sets {
+            if weapon_set.checked_weapon_type().is_none() {
+                return Err(io::Error::new(io::ErrorKind::InvalidData,
+                    format!("{}: Invalid weapon set {:?}", filename.as_ref().display(), weapon_set.weapons)));
+            }
+        }
+        build.validate_gear()?;
+        if runes::by_name(&build.runes).is_none() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Unknown runes {}", build.runes)));
+        }
+        if build.specializations.len() > 3 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "At most three specializations can be selected"));
+        }
+        for specialization in &build.specializations {
+            for &selected_trait in &specialization.traits {
+                let line = traits::definition(selected_trait).line;
+                if line != specialization.line {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData,
+                        format!("Trait {:?} belongs to {:?}, not {:?}", selected_trait, line, specialization.line)));
+                }
+            }
+        }
+
+        Ok(build)
+    }
+
+    /// Checks that the attribute values of every armor piece, trinket and weapon are known
+    /// for its prefix, slot and rarity.
+    pub fn validate_gear(&self) -> io::Result<()> {
+        let equipment = self.equipment.items().map(|(slot, item)| (slot, item.clone()));
+        let weapons = self.weapon_sets.iter().map(|x| x.item());
+        for (slot, item) in equipment.iter().cloned().chain(weapons) {
+            let itemstat = itemstats::by_name(&item.prefix)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("Unknown prefix {}", item.prefix)))?;
+            if itemstat.attributes(slot, item.rarity).is_none() {
+                return Err(io::Error::new(io::ErrorKind::InvalidData,
+                    format!("Attribute values of {:?} {} {:?} are not known", item.rarity, item.prefix, slot)));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn sigils(&self) -> [[Sigil; 2]; 2] {
+        [self.weapon_sets[0].sigils, self.weapon_sets[1].sigils]
+    }
+
+    pub fn traits(&self) -> Vec<Trait> {
+        self.specializations.iter().flat_map(|x| x.traits.iter().copied()).collect()
+    }
+
+    pub fn rune_set(&self) -> &'static RuneSet {
+        runes::by_name(&self.runes).unwrap_or_else(|| panic!("Unknown runes {}", self.runes))
+    }
+
+    /// Final attributes of the character, including trait stat conversions.
+    ///
+    /// Weapon attributes are taken from the starting weapon set, weapon swaps do not change attributes.
+    pub fn total_attributes(&self) -> Attributes {
+        let gear_attributes = self.gear_attributes();
+        let mut attributes = gear_attributes;
+        for selected_trait in self.traits() {
+            for modifier in traits::definition(selected_trait).modifiers {
+                if let TraitModifier::StatConversion { from, to, ratio } = *modifier {
+                    *attributes.get_mut(to) += (gear_attributes.get(from) as f64 * ratio).round() as u32;
+                }
+            }
+        }
+        attributes
+    }
+
+    /// Attributes before stat conversions.
+    fn gear_attributes(&self) -> Attributes {
+        let weapon_set = match self.starting_weapon_set {
+            WeaponSet::Land1 => &self.weapon_sets[0],
+            WeaponSet::Land2 => &self.weapon_sets[1],
+        };
+        let food = self.food.as_ref().map(|x| x.attributes).unwrap_or_default();
+        LEVEL_80_BASE_ATTRIBUTES
+            + self.equipment.attributes()
+            + weapon_set.attributes()
+            + self.rune_set().attributes
+            + self.infusions.scaled(INFUSION_ATTRIBUTES)
+            + food
+            + self.extra_attributes
+    }
+
+    pub fn to_stats<TUptimes: BuffUptimes>(&self, buff_uptimes: TUptimes) -> PlayerStats<TUptimes> {
+        let attributes = self.total_attributes();
+        let rune_set = self.rune_set();
+
+        let mut condition_durations = self.extra_condition_durations.clone();
+        condition_durations.extend(rune_set.condition_durations.iter()
+            .map(|&(condition, value)| ConditionBonus { condition, value }));
+        let mut damage_modifiers = Vec::new();
+        for effect in rune_set.effects {
+            match *effect {
+                RuneEffect::Damage(modifier) => damage_modifiers.push(modifier),
+            }
+        }
+        damage_modifiers.extend(self.extra_condition_damages.iter().map(|x| DamageModifier {
+            bucket: ModifierBucket::Multiplicative,
+            damage_types: DamageTypes::condition(x.condition),
+            requirement: ModifierRequirement::Always,
+            scaling: ModifierScaling::Flat,
+            value: x.value,
+        }));
+        for selected_trait in self.traits() {
+            for modifier in traits::definition(selected_trait).modifiers {
+                match *modifier {
+                    TraitModifier::ConditionDuration { condition: Some(condition), value } => {
+                        condition_durations.push(ConditionBonus { condition, value });
+                    }
+                    TraitModifier::ConditionDuration { condition: None, value } => {
+                        for &condition in DamagingCondition::ALL.iter() {
+                            condition_durations.push(ConditionBonus { condition, value });
+                        }
+                    }
+                    TraitModifier::Damage(modifier) => damage_modifiers.push(modifier),
+                    // Applied in total_attributes
+                    TraitModifier::StatConversion { .. } => {}
+                }
+            }
+        }
+
+        let mut stats = PlayerStats {
+            power: attributes.power,
+            precision: attributes.precision,
+            ferocity: attributes.ferocity,
+            condition_damage: attributes.condition_damage,
+            expertise: attributes.expertise,
+            concentration: attributes.concentration,
+            extra_critical_chance: self.extra_critical_chance,
+            set_1_sigils: self.weapon_sets[0].sigils,
+            set_2_sigils: self.weapon_sets[1].sigils,
+            extra_condition_durations_all: self.extra_condition_durations_all
+                + rune_set.condition_duration_all
+                + self.food.as_ref().map(|x| x.condition_duration_all).unwrap_or(0.),
+            extra_condition_durations: Default::default(),
+            extra_condition_durations_under_buff: self.extra_condition_durations_under_buff.iter()
+                .map(|x| (x.condition.to_id(), (x.buff, x.extra_duration)))
+                .collect(),
+            damage_modifiers,
+            rune_procs: rune_set.procs,
+            weapon_set: self.starting_weapon_set,
+            weapon_set_types: [self.weapon_sets[0].weapon_type(), self.weapon_sets[1].weapon_type()],
+            weapon_strengths: [self.weapon_sets[0].weapons[0].average_strength(), self.weapon_sets[1].weapons[0].average_strength()],
+            buff_uptimes,
+        };
+        for bonus in condition_durations {
+            *stats.extra_condition_durations.entry(bonus.condition.to_id()).or_insert(0.) += bonus.value;
+        }
+
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_weapon_sets_are_load_errors() {
+        let original = fs::read_to_string("builds/renegade-original.toml").unwrap();
+        let path = std::env::temp_dir().join("optimization-invalid-weapon-set.toml");
+        fs::write(&path, original.replace(r#"weapons = ["Mace", "Axe"]"#, r#"weapons = ["Mace", "Shortbow"]"#)).unwrap();
+
+        let error = BuildFile::from_file(&path).unwrap_err();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().contains("optimization-invalid-weapon-set.toml"));
+        assert!(error.to_string().contains("[Mace, Shortbow]"));
+    }
+}
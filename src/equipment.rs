@@ -0,0 +1,101 @@
+use serde::Deserialize;
+use crate::build_file::Attributes;
+use crate::gamedata::itemstats::{self, Itemstat, Rarity, Slot};
+
+/// Attributes of a level 80 character without any equipment.
+pub const LEVEL_80_BASE_ATTRIBUTES: Attributes = Attributes {
+    power: 1000,
+    precision: 1000,
+    ferocity: 0,
+    condition_damage: 0,
+    expertise: 0,
+    concentration: 0,
+};
+
+#[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct EquipmentItem {
+    /// Name of the itemstat, for example Viper
+    pub prefix: String,
+    pub rarity: Rarity,
+}
+
+impl EquipmentItem {
+    pub fn itemstat(&self) -> Option<&'static Itemstat> {
+        itemstats::by_name(&self.prefix)
+    }
+
+    pub fn attributes(&self, slot: Slot) -> Attributes {
+        let itemstat = self.itemstat().unwrap_or_else(|| panic!("Unknown prefix {}", self.prefix));
+        itemstat.attributes(slot, self.rarity)
+            .unwrap_or_else(|| panic!("Attribute values of {:?} {} {:?} are not known", self.rarity, self.prefix, slot))
+    }
+}
+
+/// Armor and trinkets, weapons are part of weapon sets.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Equipment {
+    pub helm: EquipmentItem,
+    pub shoulders: EquipmentItem,
+    pub coat: EquipmentItem,
+    pub gloves: EquipmentItem,
+    pub leggings: EquipmentItem,
+    pub boots: EquipmentItem,
+    pub back: EquipmentItem,
+    pub accessory_1: EquipmentItem,
+    pub accessory_2: EquipmentItem,
+    pub amulet: EquipmentItem,
+    pub ring_1: EquipmentItem,
+    pub ring_2: EquipmentItem,
+}
+
+impl Equipment {
+    pub fn items(&self) -> [(Slot, &EquipmentItem); 12] {
+        [
+            (Slot::Helm, &self.helm),
+            (Slot::Shoulders, &self.shoulders),
+            (Slot::Coat, &self.coat),
+            (Slot::Gloves, &self.gloves),
+            (Slot::Leggings, &self.leggings),
+            (Slot::Boots, &self.boots),
+            (Slot::Back, &self.back),
+            (Slot::Accessory, &self.accessory_1),
+            (Slot::Accessory, &self.accessory_2),
+            (Slot::Amulet, &self.amulet),
+            (Slot::Ring, &self.ring_1),
+            (Slot::Ring, &self.ring_2),
+        ]
+    }
+
+    pub fn items_mut(&mut self) -> [&mut EquipmentItem; 12] {
+        [
+            &mut self.helm,
+            &mut self.shoulders,
+            &mut self.coat,
+            &mut self.gloves,
+            &mut self.leggings,
+            &mut self.boots,
+            &mut self.back,
+            &mut self.accessory_1,
+            &mut self.accessory_2,
+            &mut self.amulet,
+            &mut self.ring_1,
+            &mut self.ring_2,
+        ]
+    }
+
+    pub fn attributes(&self) -> Attributes {
+        self.items().iter()
+            .map(|(slot, item)| item.attributes(*slot))
+            .fold(Attributes::default(), |total, x| total + x)
+    }
+}
+
+/// Attributes granted by consumed food.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct FoodBonus {
+    pub attributes: Attributes,
+    pub condition_duration_all: f64,
+}
@@ -0,0 +1,206 @@
+// Typed versions of the raw enum values in agents and combat items, names follow
+// the arcdps documentation. Values that are not known are kept as Unknown.
+
+macro_rules! evtc_enum {
+    ($(#[$meta:meta])* $name:ident: $repr:ty { $($(#[$variant_meta:meta])* $variant:ident = $value:expr,)* }) => {
+        $(#[$meta])*
+        #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+        pub enum $name {
+            $($(#[$variant_meta])* $variant,)*
+            Unknown($repr),
+        }
+
+        impl From<$repr> for $name {
+            fn from(value: $repr) -> Self {
+                match value {
+                    $($value => $name::$variant,)*
+                    value => $name::Unknown(value),
+                }
+            }
+        }
+
+        impl From<$name> for $repr {
+            fn from(value: $name) -> Self {
+                match value {
+                    $($name::$variant => $value,)*
+                    $name::Unknown(value) => value,
+                }
+            }
+        }
+    };
+}
+
+evtc_enum!(
+    /// Kind of a state change event (`is_state_change`).
+    StateChange: u8 {
+        /// Not a state change event.
+        None = 0,
+        EnterCombat = 1,
+        ExitCombat = 2,
+        ChangeUp = 3,
+        ChangeDead = 4,
+        ChangeDown = 5,
+        Spawn = 6,
+        Despawn = 7,
+        /// dst_agent is the health percentage * 100.
+        HealthUpdate = 8,
+        /// value is the server unix timestamp, buff_dmg the local one.
+        LogStart = 9,
+        /// value is the server unix timestamp, buff_dmg the local one.
+        LogEnd = 10,
+        /// dst_agent is the new weapon set id.
+        WeaponSwap = 11,
+        /// dst_agent is the new max health.
+        MaxHealthUpdate = 12,
+        /// src_agent is the recording player.
+        PointOfView = 13,
+        /// src_agent is the game language id.
+        Language = 14,
+        /// src_agent is the game build.
+        GwBuild = 15,
+        /// src_agent is the server shard id.
+        ShardId = 16,
+        Reward = 17,
+        /// Buff applied before the log started, is_buff is set to 18 as well.
+        BuffInitial = 18,
+        Position = 19,
+        Velocity = 20,
+        Facing = 21,
+        TeamChange = 22,
+        AttackTarget = 23,
+        Targetable = 24,
+        /// src_agent is the map id.
+        MapId = 25,
+        ReplInfo = 26,
+        StackActive = 27,
+        StackReset = 28,
+        Guild = 29,
+        BuffInfo = 30,
+        BuffFormula = 31,
+        SkillInfo = 32,
+        SkillTiming = 33,
+        BreakbarState = 34,
+        BreakbarPercent = 35,
+        Error = 36,
+        Tag = 37,
+        BarrierUpdate = 38,
+        StatReset = 39,
+        Extension = 40,
+        ApiDelayed = 41,
+        InstanceStart = 42,
+        TickRate = 43,
+        Last90BeforeDown = 44,
+    }
+);
+
+evtc_enum!(
+    /// Result of a physical hit (`result`).
+    CbtResult: u8 {
+        Normal = 0,
+        Critical = 1,
+        Glance = 2,
+        Block = 3,
+        Evade = 4,
+        /// The hit interrupted the target.
+        Interrupt = 5,
+        Absorb = 6,
+        /// The attacker was blinded and missed.
+        Blind = 7,
+        KillingBlow = 8,
+        Downed = 9,
+        BreakbarDamage = 10,
+        /// Internal placeholder on skill activation, not actual damage.
+        Activation = 11,
+    }
+);
+
+evtc_enum!(
+    /// Kind of a buff remove event (`is_buff_remove`).
+    BuffRemove: u8 {
+        /// Not a buff remove event.
+        None = 0,
+        /// Last or all stacks were removed, result is the number of removed stacks.
+        All = 1,
+        /// A single stack was removed.
+        Single = 2,
+        /// A single stack was removed, generated by arcdps for each stack of an All removal.
+        Manual = 3,
+    }
+);
+
+evtc_enum!(
+    /// Relation between the source and the destination agent (`iff`).
+    /// arcdps also reports unknown affinity as 2, which ends up as `Unknown(2)`.
+    Iff: u8 {
+        Friend = 0,
+        Foe = 1,
+    }
+);
+
+evtc_enum!(
+    /// Profession of a player agent (`profession`).
+    Profession: u32 {
+        Guardian = 1,
+        Warrior = 2,
+        Engineer = 3,
+        Ranger = 4,
+        Thief = 5,
+        Elementalist = 6,
+        Mesmer = 7,
+        Necromancer = 8,
+        Revenant = 9,
+    }
+);
+
+evtc_enum!(
+    /// Elite specialization of a player agent (`is_elite`), 0 is used for core professions.
+    /// Old arcdps versions report 1 for any Heart of Thorns specialization, which ends up as `Unknown(1)`.
+    EliteSpec: u32 {
+        Druid = 5,
+        Daredevil = 7,
+        Berserker = 18,
+        Dragonhunter = 27,
+        Reaper = 34,
+        Chronomancer = 40,
+        Scrapper = 43,
+        Tempest = 48,
+        Herald = 52,
+        Soulbeast = 55,
+        Weaver = 56,
+        Holosmith = 57,
+        Deadeye = 58,
+        Mirage = 59,
+        Scourge = 60,
+        Spellbreaker = 61,
+        Firebrand = 62,
+        Renegade = 63,
+    }
+);
+
+impl EliteSpec {
+    pub fn profession(&self) -> Option<Profession> {
+        match self {
+            EliteSpec::Dragonhunter | EliteSpec::Firebrand => Some(Profession::Guardian),
+            EliteSpec::Berserker | EliteSpec::Spellbreaker => Some(Profession::Warrior),
+            EliteSpec::Scrapper | EliteSpec::Holosmith => Some(Profession::Engineer),
+            EliteSpec::Druid | EliteSpec::Soulbeast => Some(Profession::Ranger),
+            EliteSpec::Daredevil | EliteSpec::Deadeye => Some(Profession::Thief),
+            EliteSpec::Tempest | EliteSpec::Weaver => Some(Profession::Elementalist),
+            EliteSpec::Chronomancer | EliteSpec::Mirage => Some(Profession::Mesmer),
+            EliteSpec::Reaper | EliteSpec::Scourge => Some(Profession::Necromancer),
+            EliteSpec::Herald | EliteSpec::Renegade => Some(Profession::Revenant),
+            EliteSpec::Unknown(_) => None,
+        }
+    }
+}
+
+evtc_enum!(
+    /// Game client language from the language statechange.
+    Language: u64 {
+        English = 0,
+        French = 2,
+        German = 3,
+        Spanish = 4,
+        Chinese = 5,
+    }
+);
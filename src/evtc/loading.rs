@@ -0,0 +1,48 @@
+use std::fs;
+use std::io;
+use std::io::{Cursor, Read};
+use std::path::Path;
+use zip::ZipArchive;
+use zip::result::ZipError;
+
+// arcdps writes .zevtc files by default, which are zip archives
+// containing a single entry with the raw EVTC contents.
+const ZIP_MAGIC: [u8; 4] = [b'P', b'K', 0x03, 0x04];
+
+/// Reads the raw EVTC bytes from either a plain .evtc file or a zip-compressed .zevtc file.
+///
+/// The container is detected from the file contents, not the extension.
+pub fn read_log_file<P: AsRef<Path>>(path: P) -> io::Result<Vec<u8>> {
+    let bytes = fs::read(path)?;
+    if is_zip(&bytes) {
+        extract_zip(&bytes)
+    } else {
+        Ok(bytes)
+    }
+}
+
+fn is_zip(bytes: &[u8]) -> bool {
+    bytes.starts_with(&ZIP_MAGIC)
+}
+
+fn extract_zip(bytes: &[u8]) -> io::Result<Vec<u8>> {
+    let mut archive = ZipArchive::new(Cursor::new(bytes)).map_err(zip_to_io_error)?;
+    if archive.len() != 1 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Expected a single EVTC entry in the zip archive, found {}", archive.len()),
+        ));
+    }
+
+    let mut entry = archive.by_index(0).map_err(zip_to_io_error)?;
+    let mut evtc_bytes = Vec::with_capacity(entry.size() as usize);
+    entry.read_to_end(&mut evtc_bytes)?;
+    Ok(evtc_bytes)
+}
+
+fn zip_to_io_error(error: ZipError) -> io::Error {
+    match error {
+        ZipError::Io(error) => error,
+        error => io::Error::new(io::ErrorKind::InvalidData, error),
+    }
+}
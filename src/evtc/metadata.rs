@@ -0,0 +1,100 @@
+use crate::evtc::{EvtcCombatItem, StateChange, Language};
+use std::fmt;
+
+/// A calendar date in UTC.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    pub fn from_unix_timestamp(timestamp: i64) -> Self {
+        // Days to civil date conversion from http://howardhinnant.github.io/date_algorithms.html
+        let days = timestamp.div_euclid(86400) + 719468;
+        let era = days.div_euclid(146097);
+        let day_of_era = days.rem_euclid(146097);
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_index = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+        let month = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+        let year = (year_of_era + era * 400 + if month <= 2 { 1 } else { 0 }) as i32;
+
+        Date { year, month, day }
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}.{:02}.{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// Log start or end statechange.
+#[derive(Copy, Clone, Debug)]
+pub struct LogTimestamp {
+    /// Time in the same clock as combat items.
+    pub time: i64,
+    pub server_unix_timestamp: u32,
+    pub local_unix_timestamp: u32,
+}
+
+impl LogTimestamp {
+    pub fn date(&self) -> Date {
+        Date::from_unix_timestamp(self.server_unix_timestamp as i64)
+    }
+}
+
+/// Information about the log from metadata statechanges.
+/// Each of them may be missing, depending on the arcdps version and how the log ended.
+#[derive(Debug, Default)]
+pub struct LogMetadata {
+    pub log_start: Option<LogTimestamp>,
+    pub log_end: Option<LogTimestamp>,
+    pub gw2_build: Option<u64>,
+    pub language: Option<Language>,
+    pub shard_id: Option<u64>,
+    pub map_id: Option<u64>,
+    /// Address of the agent that recorded the log.
+    pub point_of_view: Option<u64>,
+}
+
+impl LogMetadata {
+    pub fn from_combat_items(combat_items: &[EvtcCombatItem]) -> Self {
+        let mut metadata = LogMetadata::default();
+        for item in combat_items {
+            match item.state_change() {
+                StateChange::LogStart => metadata.log_start = Some(LogTimestamp {
+                    time: item.time,
+                    server_unix_timestamp: item.value as u32,
+                    local_unix_timestamp: item.buff_dmg as u32,
+                }),
+                StateChange::LogEnd => metadata.log_end = Some(LogTimestamp {
+                    time: item.time,
+                    server_unix_timestamp: item.value as u32,
+                    local_unix_timestamp: item.buff_dmg as u32,
+                }),
+                StateChange::GwBuild => metadata.gw2_build = Some(item.src_agent),
+                StateChange::Language => metadata.language = Some(Language::from(item.src_agent)),
+                StateChange::ShardId => metadata.shard_id = Some(item.src_agent),
+                StateChange::MapId => metadata.map_id = Some(item.src_agent),
+                StateChange::PointOfView => metadata.point_of_view = Some(item.src_agent),
+                _ => {}
+            }
+        }
+
+        metadata
+    }
+
+    /// Duration between log start and log end in milliseconds.
+    pub fn duration(&self) -> Option<i64> {
+        Some(self.log_end?.time - self.log_start?.time)
+    }
+
+    /// Date of the log start.
+    pub fn date(&self) -> Option<Date> {
+        self.log_start.map(|x| x.date())
+    }
+}
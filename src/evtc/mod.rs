@@ -1,9 +1,16 @@
 pub mod parsing;
+pub mod loading;
+pub mod enums;
+pub mod metadata;
+
+pub use enums::{StateChange, CbtResult, BuffRemove, Iff, Profession, EliteSpec, Language};
+pub use metadata::{LogMetadata, LogTimestamp, Date};
 
 pub struct EvtcLog {
     pub version: String,
     pub revision: u8,
     pub boss_species_id: u16,
+    pub metadata: LogMetadata,
     pub agents: Vec<EvtcAgent>,
     pub skills: Vec<EvtcSkill>,
     pub combat_items: Vec<EvtcCombatItem>,
@@ -19,7 +26,13 @@ pub struct EvtcAgent {
     pub condition: i16,
     pub hitbox_width: i16,
     pub hitbox_height: i16,
+    /// Raw name, for players it contains the character name, account name and subgroup separated by NULs.
     pub name: String,
+    /// Character name for players, the same as `name` for other agents.
+    pub character_name: String,
+    /// Account name without the leading colon, only for players.
+    pub account_name: Option<String>,
+    pub subgroup: Option<u8>,
 }
 
 pub struct EvtcSkill {
@@ -54,13 +67,131 @@ pub struct EvtcCombatItem {
     pub padding: u32,
 }
 
+/// A change of max health of an agent.
+#[derive(Copy, Clone, Debug)]
+pub struct MaxHealthUpdate {
+    pub time: i64,
+    pub max_health: u64,
+}
+
+/// All max health changes of an agent, ordered by time.
+pub struct MaxHealthHistory {
+    updates: Vec<MaxHealthUpdate>,
+}
+
+impl MaxHealthHistory {
+    pub fn updates(&self) -> &[MaxHealthUpdate] {
+        &self.updates
+    }
+
+    /// Max health at the given time. Events before the first update use the first known value.
+    pub fn at(&self, time: i64) -> Option<u64> {
+        self.updates.iter()
+            .take_while(|x| x.time <= time)
+            .last()
+            .or_else(|| self.updates.first())
+            .map(|x| x.max_health)
+    }
+}
+
+/// A way to find the player that is resimulated within a log.
+pub enum PlayerSelector {
+    CharacterName(String),
+    /// The account name, the leading colon is optional.
+    AccountName(String),
+    /// The player that recorded the log.
+    PointOfView,
+}
+
+impl EvtcLog {
+    /// The player that recorded the log.
+    pub fn point_of_view(&self) -> Option<&EvtcAgent> {
+        let address = self.metadata.point_of_view?;
+        self.agents.iter().find(|x| x.address == address)
+    }
+
+    pub fn find_player(&self, selector: &PlayerSelector) -> Option<&EvtcAgent> {
+        match selector {
+            PlayerSelector::CharacterName(name) => self.agents.iter()
+                .find(|x| x.is_player() && x.character_name == *name),
+            PlayerSelector::AccountName(name) => {
+                let name = name.trim_start_matches(':');
+                self.agents.iter()
+                    .find(|x| x.is_player() && x.account_name.as_deref() == Some(name))
+            }
+            PlayerSelector::PointOfView => self.point_of_view(),
+        }
+    }
+
+    pub fn max_health_history(&self, agent: &EvtcAgent) -> MaxHealthHistory {
+        let mut updates: Vec<_> = self.combat_items.iter()
+            .filter(|x| x.state_change() == StateChange::MaxHealthUpdate && x.src_agent == agent.address)
+            .map(|x| MaxHealthUpdate { time: x.time, max_health: x.dst_agent })
+            .collect();
+        updates.sort_by_key(|x| x.time);
+
+        MaxHealthHistory { updates }
+    }
+}
+
+/// Classification of an agent based on its `profession` and `is_elite` values.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AgentKind {
+    /// Elite specialization is None for core professions.
+    Player { profession: Profession, elite_spec: Option<EliteSpec> },
+    Npc { species_id: u16 },
+    /// Gadgets do not have a species id, only a volatile id that may change between game builds.
+    Gadget { volatile_id: u16 },
+}
+
 impl EvtcAgent {
     pub fn is_player(&self) -> bool {
         self.is_elite != 0xff_ff_ff_ff
     }
+
+    pub fn is_gadget(&self) -> bool {
+        !self.is_player() && self.profession >> 16 == 0xff_ff
+    }
+
+    pub fn kind(&self) -> AgentKind {
+        if self.is_player() {
+            AgentKind::Player {
+                profession: Profession::from(self.profession),
+                elite_spec: if self.is_elite == 0 { None } else { Some(EliteSpec::from(self.is_elite)) },
+            }
+        } else if self.is_gadget() {
+            AgentKind::Gadget { volatile_id: self.profession as u16 }
+        } else {
+            AgentKind::Npc { species_id: self.profession as u16 }
+        }
+    }
+
+    /// Species id for NPCs, None for players and gadgets.
+    pub fn species_id(&self) -> Option<u16> {
+        match self.kind() {
+            AgentKind::Npc { species_id } => Some(species_id),
+            _ => None,
+        }
+    }
 }
 
 impl EvtcCombatItem {
+    pub fn state_change(&self) -> StateChange {
+        StateChange::from(self.is_state_change)
+    }
+
+    pub fn hit_result(&self) -> CbtResult {
+        CbtResult::from(self.result)
+    }
+
+    pub fn buff_remove_kind(&self) -> BuffRemove {
+        BuffRemove::from(self.is_buff_remove)
+    }
+
+    pub fn iff_kind(&self) -> Iff {
+        Iff::from(self.iff)
+    }
+
     /// Does not include initial buff statechange.
     pub fn is_buff_apply(&self) -> bool {
         self.is_buff > 0 && self.buff_dmg == 0 && self.is_state_change == 0 && self.is_activation == 0 && self.is_buff_remove == 0 && self.value != 0
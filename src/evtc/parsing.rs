@@ -1,9 +1,11 @@
 use nom::IResult;
 use nom::number::complete::*;
 use std::str::{from_utf8, Utf8Error};
+use std::fmt;
+use std::error::Error;
 use nom::bytes::complete::take;
-use nom::multi::many0;
-use crate::evtc::{EvtcAgent, EvtcSkill, EvtcCombatItem, EvtcLog};
+use nom::combinator::map_res;
+use crate::evtc::{EvtcAgent, EvtcSkill, EvtcCombatItem, EvtcLog, LogMetadata};
 
 // log metadata
 // 12 bytes: arc build version (string)
@@ -24,6 +26,7 @@ use crate::evtc::{EvtcAgent, EvtcSkill, EvtcCombatItem, EvtcLog};
 // - i16 condition
 // - i16 hitbox_height
 // - 68 bytes name (utf-8 string)
+//   for players, this is "character name\0:account name\0subgroup\0"
 
 // skills
 // i32: skill count
@@ -32,13 +35,80 @@ use crate::evtc::{EvtcAgent, EvtcSkill, EvtcCombatItem, EvtcLog};
 // 64 bytes: name
 
 // combat items
-// 64 bytes until end
+// 64 bytes until end, layout depends on the revision:
+// - revision 0: u16 overstack value and skill id, no dst master instance id,
+//   9 bytes of internal tracking garbage before iff, 2 more at the end,
+//   no offcycle and no padding (buff stack id)
+// - revision 1: the layout of EvtcCombatItem
+
+const HEADER_SIZE: usize = 16;
+const AGENT_SIZE: usize = 96;
+const AGENT_NAME_OFFSET: usize = 28;
+const SKILL_SIZE: usize = 68;
+const SKILL_NAME_OFFSET: usize = 4;
+const COMBAT_ITEM_SIZE: usize = 64;
+
+/// Reasons an EVTC log may fail to parse. All offsets are in bytes from the start of the log.
+#[derive(Debug, Eq, PartialEq)]
+pub enum EvtcParseError {
+    /// The revision byte in the header is not one we can parse.
+    UnsupportedRevision { revision: u8, offset: usize },
+    /// The log ends before the header is complete.
+    TruncatedHeader { offset: usize },
+    /// The log ends before all agents announced by the agent count were read.
+    TruncatedAgents { offset: usize, expected: u32, parsed: u32 },
+    /// The log ends before all skills announced by the skill count were read.
+    TruncatedSkills { offset: usize, expected: u32, parsed: u32 },
+    /// The log ends with bytes that do not form a complete combat item.
+    PartialCombatItem { offset: usize, length: usize },
+    /// An agent or skill name is not valid UTF-8.
+    InvalidUtf8Name { offset: usize },
+}
+
+impl fmt::Display for EvtcParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvtcParseError::UnsupportedRevision { revision, offset } =>
+                write!(f, "unsupported EVTC revision {} at offset {}", revision, offset),
+            EvtcParseError::TruncatedHeader { offset } =>
+                write!(f, "log ends in the header at offset {}", offset),
+            EvtcParseError::TruncatedAgents { offset, expected, parsed } =>
+                write!(f, "log ends in the agent table at offset {} ({} of {} agents read)", offset, parsed, expected),
+            EvtcParseError::TruncatedSkills { offset, expected, parsed } =>
+                write!(f, "log ends in the skill table at offset {} ({} of {} skills read)", offset, parsed, expected),
+            EvtcParseError::PartialCombatItem { offset, length } =>
+                write!(f, "log ends with a partial combat item of {} bytes at offset {}", length, offset),
+            EvtcParseError::InvalidUtf8Name { offset } =>
+                write!(f, "invalid UTF-8 in name at offset {}", offset),
+        }
+    }
+}
+
+impl Error for EvtcParseError {}
+
 
 
 fn parse_arc_string(i: &[u8]) -> Result<String, Utf8Error> {
     from_utf8(i).map(|str| str.trim_matches(|c| c == '\0').to_string())
 }
 
+fn split_agent_name(name: &str, is_elite: u32) -> (String, Option<String>, Option<u8>) {
+    if is_elite == 0xff_ff_ff_ff {
+        // Not a player, there is only the name
+        return (name.to_string(), None, None);
+    }
+
+    let mut parts = name.split('\0');
+    let character_name = parts.next().unwrap_or("").to_string();
+    let account_name = parts.next()
+        .map(|x| x.trim_start_matches(':'))
+        .filter(|x| !x.is_empty())
+        .map(|x| x.to_string());
+    let subgroup = parts.next().and_then(|x| x.parse().ok());
+
+    (character_name, account_name, subgroup)
+}
+
 fn parse_agent(i: &[u8]) -> IResult<&[u8], EvtcAgent> {
     let (i, address) = le_u64(i)?;
     let (i, profession) = le_u32(i)?;
@@ -49,7 +119,8 @@ fn parse_agent(i: &[u8]) -> IResult<&[u8], EvtcAgent> {
     let (i, condition) = le_i16(i)?;
     let (i, hitbox_width) = le_i16(i)?;
     let (i, hitbox_height) = le_i16(i)?;
-    let (i, name_bytes) = take(68usize)(i)?;
+    let (i, name) = map_res(take(68usize), parse_arc_string)(i)?;
+    let (character_name, account_name, subgroup) = split_agent_name(&name, is_elite);
 
     Ok((i, EvtcAgent {
         address,
@@ -61,31 +132,76 @@ fn parse_agent(i: &[u8]) -> IResult<&[u8], EvtcAgent> {
         condition,
         hitbox_width,
         hitbox_height,
-        name: parse_arc_string(name_bytes).unwrap_or("Invalid UTF-8 in name".to_string()),
+        name,
+        character_name,
+        account_name,
+        subgroup,
     }))
 }
-fn parse_agents(i: &[u8]) -> IResult<&[u8], Vec<EvtcAgent>> {
-    let (i, count) = le_i32(i)?;
-    let (i, agents) = nom::multi::count(parse_agent, count as usize)(i)?;
-
-    Ok((i, agents))
-}
 
 fn parse_skill(i: &[u8]) -> IResult<&[u8], EvtcSkill> {
     let (i, id) = le_i32(i)?;
-    let (i, name_bytes) = take(64usize)(i)?;
+    let (i, name) = map_res(take(64usize), parse_arc_string)(i)?;
 
     Ok((i, EvtcSkill {
         id,
-        name: parse_arc_string(name_bytes).unwrap_or("Invalid UTF-8 in name".to_string()),
+        name,
     }))
 }
 
-fn parse_skills(i: &[u8]) -> IResult<&[u8], Vec<EvtcSkill>> {
-    let (i, count) = le_i32(i)?;
-    let (i, skills) = nom::multi::count(parse_skill, count as usize)(i)?;
+/// Parses a revision 0 combat item, normalizing it into the revision 1 structure.
+/// Fields that do not exist in revision 0 are set to 0.
+fn parse_combat_item_rev0(i: &[u8]) -> IResult<&[u8], EvtcCombatItem> {
+    let (i, time) = le_i64(i)?;
+    let (i, src_agent) = le_u64(i)?;
+    let (i, dst_agent) = le_u64(i)?;
+    let (i, value) = le_i32(i)?;
+    let (i, buff_dmg) = le_i32(i)?;
+    let (i, overstack_value) = le_u16(i)?;
+    let (i, skill_id) = le_u16(i)?;
+    let (i, src_inst_id) = le_u16(i)?;
+    let (i, dst_inst_id) = le_u16(i)?;
+    let (i, src_master_inst_id) = le_u16(i)?;
+    let (i, _) = take(9usize)(i)?; // internal tracking
+    let (i, iff) = u8(i)?;
+    let (i, buff) = u8(i)?;
+    let (i, result) = u8(i)?;
+    let (i, is_activation) = u8(i)?;
+    let (i, is_buff_remove) = u8(i)?;
+    let (i, is_ninety) = u8(i)?;
+    let (i, is_fifty) = u8(i)?;
+    let (i, is_moving) = u8(i)?;
+    let (i, is_state_change) = u8(i)?;
+    let (i, is_flanking) = u8(i)?;
+    let (i, is_shields) = u8(i)?;
+    let (i, _) = take(2usize)(i)?; // internal tracking
 
-    Ok((i, skills))
+    Ok((i, EvtcCombatItem {
+        time,
+        src_agent,
+        dst_agent,
+        value,
+        buff_dmg,
+        overstack_value: overstack_value as u32,
+        skill_id: skill_id as u32,
+        src_inst_id,
+        dst_inst_id,
+        src_master_inst_id,
+        dst_master_inst_id: 0,
+        iff,
+        is_buff: buff,
+        result,
+        is_activation,
+        is_buff_remove,
+        is_ninety,
+        is_fifty,
+        is_moving,
+        is_state_change,
+        is_flanking,
+        is_shields,
+        is_offcycle: 0,
+        padding: 0,
+    }))
 }
 
 fn parse_combat_item(i: &[u8]) -> IResult<&[u8], EvtcCombatItem> {
@@ -142,29 +258,85 @@ fn parse_combat_item(i: &[u8]) -> IResult<&[u8], EvtcCombatItem> {
     }))
 }
 
-fn parse_combat_items(i: &[u8]) -> IResult<&[u8], Vec<EvtcCombatItem>> {
-    many0(parse_combat_item)(i)
+/// Parses a table of `count` fixed-size items, mapping failures to a truncation error,
+/// or to an UTF-8 error if the item was complete and only its name could not be decoded.
+fn parse_table<'a, T>(
+    input: &'a [u8],
+    i: &'a [u8],
+    item_size: usize,
+    name_offset: usize,
+    parser: fn(&[u8]) -> IResult<&[u8], T>,
+    truncated: fn(usize, u32, u32) -> EvtcParseError,
+) -> Result<(&'a [u8], Vec<T>), EvtcParseError> {
+    let (mut i, count) = le_u32::<_, ()>(i).map_err(|_| truncated(offset(input, i), 0, 0))?;
+    let mut items = Vec::new();
+    for parsed in 0..count {
+        let item_offset = offset(input, i);
+        let (next_i, item) = parser(i).map_err(|_| {
+            if i.len() < item_size {
+                truncated(item_offset, count, parsed)
+            } else {
+                EvtcParseError::InvalidUtf8Name { offset: item_offset + name_offset }
+            }
+        })?;
+        items.push(item);
+        i = next_i;
+    }
+
+    Ok((i, items))
+}
+
+fn parse_combat_items(input: &[u8], i: &[u8], revision: u8) -> Result<Vec<EvtcCombatItem>, EvtcParseError> {
+    let trailing_length = i.len() % COMBAT_ITEM_SIZE;
+    if trailing_length != 0 {
+        return Err(EvtcParseError::PartialCombatItem {
+            offset: offset(input, i) + i.len() - trailing_length,
+            length: trailing_length,
+        });
+    }
+
+    let parser = match revision {
+        0 => parse_combat_item_rev0,
+        _ => parse_combat_item,
+    };
+    let combat_items = i.chunks_exact(COMBAT_ITEM_SIZE)
+        .map(|chunk| parser(chunk).expect("Combat item chunk has the exact size").1)
+        .collect();
+    Ok(combat_items)
+}
+
+fn offset(input: &[u8], remaining: &[u8]) -> usize {
+    input.len() - remaining.len()
 }
 
-pub fn evtc_parser(i: &[u8]) -> IResult<&[u8], EvtcLog> {
-    let (i, version_bytes) = nom::bytes::complete::take(12usize)(i)?;
-    let (i, revision) = u8(i)?;
-    if revision != 1 {
-        // The API for custom errors is insane, TODO: fix properly
-        panic!("Only revision 1 is supported");
+/// Parses a whole uncompressed EVTC log.
+pub fn parse_evtc(input: &[u8]) -> Result<EvtcLog, EvtcParseError> {
+    if input.len() < HEADER_SIZE {
+        return Err(EvtcParseError::TruncatedHeader { offset: input.len() });
+    }
+    let (header, i) = input.split_at(HEADER_SIZE);
+    let version_bytes = &header[0..12];
+    let revision = header[12];
+    if revision > 1 {
+        return Err(EvtcParseError::UnsupportedRevision { revision, offset: 12 });
     }
-    let (i, boss_species_id) = le_u16(i)?;
-    let (i, _) = u8(i)?; // unused byte
-    let (i, agents) = parse_agents(i)?;
-    let (i, skills) = parse_skills(i)?;
-    let (i, combat_items) = parse_combat_items(i)?;
+    let boss_species_id = u16::from_le_bytes([header[13], header[14]]);
+    // header[15] is unused
 
-    Ok((i, EvtcLog {
+    let (i, agents) = parse_table(input, i, AGENT_SIZE, AGENT_NAME_OFFSET, parse_agent,
+                                  |offset, expected, parsed| EvtcParseError::TruncatedAgents { offset, expected, parsed })?;
+    let (i, skills) = parse_table(input, i, SKILL_SIZE, SKILL_NAME_OFFSET, parse_skill,
+                                  |offset, expected, parsed| EvtcParseError::TruncatedSkills { offset, expected, parsed })?;
+    let combat_items = parse_combat_items(input, i, revision)?;
+    let metadata = LogMetadata::from_combat_items(&combat_items);
+
+    Ok(EvtcLog {
         version: parse_arc_string(version_bytes).unwrap_or("???".to_string()),
         revision,
         boss_species_id,
+        metadata,
         agents,
         skills,
         combat_items
-    }))
+    })
 }
@@ -1,37 +1,154 @@
-use crate::{gamedata, SimulationEvent, LogBuffUptimes, PlayerStats, Sigil, BuffUptimes, DamagingCondition, ids, LifeStealSource, PhysicalHitSource, ConditionApplicationSource, WeaponSet};
-use crate::evtc::{EvtcCombatItem, EvtcLog, EvtcAgent};
-use crate::gamedata::{SkillType, SkillData};
+use crate::{gamedata, stats, HitResult, SimulationEvent, LogBuffUptimes, PlayerStats, Sigil, BuffUptimes, DamagingCondition, ids, LifeStealSource, PhysicalHitSource, ConditionApplicationSource, WeaponSet, BuffTarget};
+use crate::evtc::{EvtcCombatItem, EvtcLog, EvtcAgent, StateChange, CbtResult, BuffRemove, AgentKind, Profession, EliteSpec};
+use crate::gamedata::{sigils, SkillType, GamedataSnapshot};
+use crate::gamedata::procs::{ProcDefinition, ProcEffect};
+use crate::modifiers::DamageType;
+use crate::sim::DamageDistribution;
+use crate::gamedata::minions::{self, MinionAttributes};
+use std::collections::HashMap;
 use std::ops::Range;
-use crate::SimulationEvent::{ConditionTick, PhysicalHit, SelfBuffApplication, TargetConditionApplication, TargetBuffApplication};
+use crate::SimulationEvent::{BuffRemoval, ConditionTick, PhysicalHit, SelfBuffApplication, TargetConditionApplication, TargetBuffApplication, MinionDamage};
 use itertools::Itertools;
 
 const TRACKED_DAMAGING_CONDITION_IDS: [u32; 5] = [ids::skills::BLEEDING, ids::skills::BURNING, ids::skills::CONFUSION, ids::skills::POISONED, ids::skills::TORMENT];
-const TRACKED_PLAYER_BUFF_IDS: [u32; 3] = [ids::skills::FURY, ids::skills::MIGHT, ids::skills::KALLAS_FERVOR];
-const TRACKED_TARGET_BUFF_IDS: [u32; 1] = [ids::skills::VULNERABILITY];
+const TRACKED_PLAYER_BUFF_IDS: [u32; 13] = [
+    ids::skills::PROTECTION, ids::skills::REGENERATION, ids::skills::SWIFTNESS, ids::skills::FURY,
+    ids::skills::VIGOR, ids::skills::MIGHT, ids::skills::AEGIS, ids::skills::RESOLUTION,
+    ids::skills::STABILITY, ids::skills::QUICKNESS, ids::skills::RESISTANCE, ids::skills::ALACRITY,
+    ids::skills::KALLAS_FERVOR,
+];
+const TRACKED_TARGET_BUFF_IDS: [u32; 9] = [
+    ids::skills::VULNERABILITY, ids::skills::BLINDED, ids::skills::CRIPPLED, ids::skills::CHILLED,
+    ids::skills::IMMOBILE, ids::skills::WEAKNESS, ids::skills::SLOW, ids::skills::FEAR, ids::skills::TAUNT,
+];
+/// Removals with less remaining duration than one server tick are treated as natural expiry.
+const EXPIRY_TOLERANCE: i32 = 40;
+
+/// Only Renegade skills, traits and buffs are modelled, other players would resimulate incorrectly.
+pub fn is_supported_player(player: &EvtcAgent) -> bool {
+    player.kind() == AgentKind::Player { profession: Profession::Revenant, elite_spec: Some(EliteSpec::Renegade) }
+}
+
+/// Who caused an event, minions of the player are resolved through their master instance id.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum EventSource {
+    Player,
+    Minion(MinionAttributes),
+    Other,
+}
+
+struct PlayerSources<'a> {
+    player: &'a EvtcAgent,
+    inst_id: u16,
+    species_ids: HashMap<u64, Option<u16>>,
+}
+
+impl<'a> PlayerSources<'a> {
+    fn new(evtc_log: &EvtcLog, player: &'a EvtcAgent) -> Self {
+        let inst_id = evtc_log.combat_items
+            .iter()
+            .find(|x| x.state_change() == StateChange::None && x.src_agent == player.address)
+            .expect("Found no event with player as src_agent")
+            .src_inst_id;
+        let species_ids = evtc_log.agents.iter().map(|x| (x.address, x.species_id())).collect();
+        PlayerSources { player, inst_id, species_ids }
+    }
+
+    fn resolve(&self, agent: u64, master_inst_id: u16) -> EventSource {
+        if agent == self.player.address {
+            EventSource::Player
+        } else if master_inst_id != 0 && master_inst_id == self.inst_id {
+            let species_id = self.species_ids.get(&agent).copied().flatten();
+            EventSource::Minion(minions::attributes(species_id))
+        } else {
+            EventSource::Other
+        }
+    }
+
+    fn source(&self, event: &EvtcCombatItem) -> EventSource {
+        self.resolve(event.src_agent, event.src_master_inst_id)
+    }
+
+    /// Who applied the stacks of a buff removal, removals have the source of the stacks as their destination.
+    fn removed_stack_source(&self, event: &EvtcCombatItem) -> EventSource {
+        self.resolve(event.dst_agent, event.dst_master_inst_id)
+    }
 
+    /// The player or a minion that is resimulated as if the player caused the event.
+    fn acts_as_player(&self, event: &EvtcCombatItem) -> bool {
+        matches!(self.source(event), EventSource::Player | EventSource::Minion(MinionAttributes::Owner))
+    }
 
-pub fn get_events(evtc_log: &EvtcLog, player: &EvtcAgent, target: &EvtcAgent, mut stats: PlayerStats<LogBuffUptimes>, gamedata: &SkillData) -> Vec<SimulationEvent> {
+    /// Removal of stacks that the player or a resimulated minion applied.
+    fn removed_stack_acts_as_player(&self, event: &EvtcCombatItem) -> bool {
+        matches!(self.removed_stack_source(event), EventSource::Player | EventSource::Minion(MinionAttributes::Owner))
+    }
+}
+
+/// Recognizes condition applications of the original rune procs by their condition and base duration,
+/// a proc is not recognized again before its cooldown ends.
+struct RuneProcAttribution {
+    procs: &'static [ProcDefinition],
+    /// Time and attributed stacks of the last proc of each definition.
+    last_procs: Vec<Option<(i64, u32)>>,
+}
+
+impl RuneProcAttribution {
+    fn new(procs: &'static [ProcDefinition]) -> Self {
+        // Simulated strikes of rune procs would need the hits of the log to be attributed as well
+        assert!(procs.iter().flat_map(|x| x.effects).all(|x| !matches!(x, ProcEffect::Strike { .. })),
+                "Rune procs with strikes are not supported in the original log");
+        RuneProcAttribution { procs, last_procs: vec![None; procs.len()] }
+    }
+
+    fn attribute(&mut self, applied_condition: DamagingCondition, applied_duration: u32, time: i64) -> bool {
+        for (definition, last_proc) in self.procs.iter().zip(self.last_procs.iter_mut()) {
+            for effect in definition.effects {
+                let (condition, stacks, base_duration) = match *effect {
+                    ProcEffect::Condition { condition, stacks, base_duration } => (condition, stacks, base_duration),
+                    ProcEffect::ConditionOnNextHit { condition, stacks, base_duration } => (condition, stacks, base_duration),
+                    ProcEffect::Strike { .. } => continue,
+                };
+                if condition != applied_condition || base_duration != applied_duration {
+                    continue;
+                }
+                match last_proc {
+                    Some((proc_time, attributed_stacks)) if *proc_time == time && *attributed_stacks < stacks => {
+                        *attributed_stacks += 1;
+                        return true;
+                    }
+                    Some((proc_time, _)) if time < *proc_time + definition.cooldown => {}
+                    _ => {
+                        *last_proc = Some((time, 1));
+                        return true;
+                    }
+                }
+            }
+        }
+        false
+    }
+}
+
+pub fn get_events(evtc_log: &EvtcLog, player: &EvtcAgent, target: &EvtcAgent, mut stats: PlayerStats<LogBuffUptimes>, gamedata: &GamedataSnapshot) -> Vec<SimulationEvent> {
     let mut last_condition_tick = 0;
     let mut target_health = 1.;
 
     // This is only used to reverse values when building the representation
     let mut target_buffs = LogBuffUptimes { stack_counts: Default::default() };
     let mut simulation_events = Vec::new();
-    let mut player_inst_id = evtc_log.combat_items
-        .iter()
-        .filter(|x| x.is_state_change == 0 && x.src_agent == player.address)
-        .next()
-        .expect("Found no event with player as src_agent")
-        .src_inst_id;
-
-    // There's no implementation for reversing the other sigils.
+    let sources = PlayerSources::new(evtc_log, player);
+    let mut warned_unknown_hit_result = false;
+
+    // There's no implementation for reversing the procs of other sigils, passive sigils are reversed with the damage modifiers.
     // Notably, anything that may cause overstacks (vuln) will be resimmed wrong because overstacked applications will be missing.
-    assert!(stats.set_1_sigils.iter().all(|x| *x == Sigil::Earth || *x == Sigil::Doom || *x == Sigil::Geomancy || *x == Sigil::Frailty));
+    let is_reversible_sigil = |x: &Sigil| *x == Sigil::Earth || *x == Sigil::Doom || *x == Sigil::Geomancy || sigils::definition(*x).procs.is_empty();
+    assert!(stats.set_1_sigils.iter().chain(stats.set_2_sigils.iter()).all(is_reversible_sigil));
+    let mut rune_procs = RuneProcAttribution::new(stats.rune_procs);
     if stats.set_1_sigils.iter().all(|x| *x == Sigil::Frailty) {
         eprintln!("Frailty sigil in original log, may cause wrong resimulation because of missing overstacked applications!!")
     }
 
-    fn get_same_time_events(events: &Vec<&EvtcCombatItem>, i: usize, delta: i64) -> Range<usize> {
+    fn get_same_time_events(events: &[&EvtcCombatItem], i: usize, delta: i64) -> Range<usize> {
         let time = events[i].time;
         let mut min_i = i;
         let mut max_i = i;
@@ -73,17 +190,15 @@ pub fn get_events(evtc_log: &EvtcLog, player: &EvtcAgent, target: &EvtcAgent, mu
     eprintln!("WARNING: The extraction currently expects unique condition durations, especially for the Earth sigil. If there is any non-Earth 6s bleed application, it will be sourced incorrectly!");
 
     for (i, event) in sorted_events.iter().enumerate() {
-        if event.is_state_change == 8 && event.src_agent == target.address {
-            // Health update (statechange 8) for the target
+        if event.state_change() == StateChange::HealthUpdate && event.src_agent == target.address {
+            // Health update for the target
             target_health = event.dst_agent as f64 / 10000.;
-        } else if (event.is_state_change == 18 && event.is_buff == 18) || event.is_buff_apply() {
-            // Initial buff event (statechange 18) or a buff apply event
+        } else if (event.state_change() == StateChange::BuffInitial && event.is_buff == 18) || event.is_buff_apply() {
+            // Initial buff event or a buff apply event
             let skill_id = event.skill_id;
             let agent = event.dst_agent;
             let source_agent = event.src_agent;
-            let active = event.is_shields > 0;
             let duration = event.value as u32;
-            let buff_stack_id = event.padding;
 
             if TRACKED_PLAYER_BUFF_IDS.contains(&skill_id)
                 && source_agent == player.address && agent == player.address {
@@ -99,7 +214,7 @@ pub fn get_events(evtc_log: &EvtcLog, player: &EvtcAgent, target: &EvtcAgent, mu
             if TRACKED_DAMAGING_CONDITION_IDS.contains(&skill_id) {
                 assert_eq!(event.is_offcycle, 0, "Boon extensions are not supported");
                 assert!(gamedata::get_skill_type(skill_id) == SkillType::Condition);
-                if source_agent == player.address && agent == target.address {
+                if sources.acts_as_player(event) && agent == target.address {
                     let base_duration = get_base_duration(&mut stats, skill_id, duration, event.time);
                     // Misery Swipe (mace aa1) 3s torment
                     // Anguish Swipe (mace aa2) 3s torment
@@ -139,7 +254,7 @@ pub fn get_events(evtc_log: &EvtcLog, player: &EvtcAgent, target: &EvtcAgent, mu
                             }
                         }
                         if ring_of_earth_found && candidate_bleeds == 3 {
-                            source = ConditionApplicationSource::Skill(ids::skills::RING_OF_EARTH);
+                            source = ConditionApplicationSource::Sigil { sigil: Sigil::Geomancy, weapon_set: stats.weapon_set };
                         } else if ring_of_earth_found && candidate_bleeds > 3 {
                             unimplemented!("Only marking the first 3 bleed stacks as Geomancy effect is not implemented yet.")
                         }
@@ -159,7 +274,7 @@ pub fn get_events(evtc_log: &EvtcLog, player: &EvtcAgent, target: &EvtcAgent, mu
                             }
                         }
                         if doom_removal_found && candidate_poisons == 3 {
-                            source = ConditionApplicationSource::Sigil(Sigil::Doom);
+                            source = ConditionApplicationSource::Sigil { sigil: Sigil::Doom, weapon_set: stats.weapon_set };
                         } else if doom_removal_found && candidate_poisons > 3 {
                             unimplemented!("Only marking the first 3 poison stacks as Doom effect is not implemented yet.")
                         }
@@ -167,7 +282,13 @@ pub fn get_events(evtc_log: &EvtcLog, player: &EvtcAgent, target: &EvtcAgent, mu
 
                     if skill_id == ids::skills::BLEEDING && base_duration == 6000 {
                         // Earth candidate
-                        source = ConditionApplicationSource::Sigil(Sigil::Earth);
+                        source = ConditionApplicationSource::Sigil { sigil: Sigil::Earth, weapon_set: stats.weapon_set };
+                    }
+
+                    if let ConditionApplicationSource::Unknown = source {
+                        if rune_procs.attribute(DamagingCondition::from_id(skill_id), base_duration, event.time) {
+                            source = ConditionApplicationSource::Rune;
+                        }
                     }
                     simulation_events.push(TargetConditionApplication {
                         time: event.time,
@@ -179,18 +300,20 @@ pub fn get_events(evtc_log: &EvtcLog, player: &EvtcAgent, target: &EvtcAgent, mu
             }
 
             if TRACKED_TARGET_BUFF_IDS.contains(&skill_id)
-                && source_agent == player.address && agent == target.address {
+                && sources.acts_as_player(event) && agent == target.address {
                 assert_eq!(event.is_offcycle, 0, "Boon extensions are not supported");
                 let base_duration = get_base_duration(&mut stats, skill_id, duration, event.time);
 
                 target_buffs.add_stack(skill_id, duration as i64, event.time);
                 simulation_events.push(TargetBuffApplication { time: event.time, skill_id, base_duration });
             }
-        } else if event.is_state_change == 11 {
+        } else if event.state_change() == StateChange::WeaponSwap {
             if event.src_agent == player.address {
                 if event.dst_agent == 4 {
+                    stats.weapon_set = WeaponSet::Land1;
                     simulation_events.push(SimulationEvent::WeaponSwap { time: event.time, weapon_set: WeaponSet::Land1 });
                 } else if event.dst_agent == 5 {
+                    stats.weapon_set = WeaponSet::Land2;
                     simulation_events.push(SimulationEvent::WeaponSwap { time: event.time, weapon_set: WeaponSet::Land2 });
                 }
                 // Other weapon sets are ignored
@@ -198,60 +321,90 @@ pub fn get_events(evtc_log: &EvtcLog, player: &EvtcAgent, target: &EvtcAgent, mu
         } else if event.is_buff_remove() {
             let skill_id = event.skill_id;
             let target_agent = event.src_agent;
-            let remover_agent = event.dst_agent;
-            let stack_count = event.result;
-            if TRACKED_PLAYER_BUFF_IDS.contains(&skill_id) && target_agent == player.address {
-                if event.is_buff_remove == 1 {
-                    // last/all stack
-                    stats.buff_uptimes.remove_last_stack(skill_id, event.time);
-                } else if event.is_buff_remove == 2 {
-                    // single stack
-                    stats.buff_uptimes.remove_stack(skill_id, event.time);
-                } else if event.is_buff_remove == 3 {
-                    // manual single stack (extra by arc) when last/all
-                    // should be ignorable?
-                } else {
-                    unreachable!("Invalid buff remove type")
-                }
-            }
-            if TRACKED_TARGET_BUFF_IDS.contains(&skill_id)
-                && target_agent == target.address {
-                if event.is_buff_remove == 1 {
-                    // last/all stack
-                    target_buffs.remove_last_stack(skill_id, event.time);
-                } else if event.is_buff_remove == 2 {
-                    // single stack
-                    target_buffs.remove_stack(skill_id, event.time);
-                } else if event.is_buff_remove == 3 {
-                    // manual single stack (extra by arc) when last/all
-                    // should be ignorable?
-                } else {
-                    unreachable!("Invalid buff remove type")
+            // Only stacks counted on application are removed, the same sources as there are accepted
+            let buff_target = if TRACKED_PLAYER_BUFF_IDS.contains(&skill_id)
+                && target_agent == player.address && sources.removed_stack_source(event) == EventSource::Player {
+                Some(BuffTarget::Player)
+            } else if TRACKED_TARGET_BUFF_IDS.contains(&skill_id)
+                && target_agent == target.address && sources.removed_stack_acts_as_player(event) {
+                Some(BuffTarget::Target)
+            } else {
+                None
+            };
+            if let Some(buff_target) = buff_target {
+                let uptimes = match buff_target {
+                    BuffTarget::Player => &mut stats.buff_uptimes,
+                    BuffTarget::Target => &mut target_buffs,
+                };
+                let all_stacks = match event.buff_remove_kind() {
+                    BuffRemove::All => {
+                        uptimes.remove_last_stack(skill_id, event.time);
+                        true
+                    }
+                    BuffRemove::Single => {
+                        uptimes.remove_stack(skill_id, event.time);
+                        false
+                    }
+                    BuffRemove::Manual => {
+                        // manual single stack (extra by arc) when last/all
+                        // should be ignorable?
+                        continue;
+                    }
+                    BuffRemove::None | BuffRemove::Unknown(_) => unreachable!("Invalid buff remove type")
+                };
+                // Value is the remaining duration of the removed stacks, expired stacks are removed by the simulation itself
+                if event.value > EXPIRY_TOLERANCE {
+                    simulation_events.push(BuffRemoval { time: event.time, skill_id, target: buff_target, all_stacks });
                 }
             }
         } else if event.is_physical_hit() {
             // Barrier is ignored, damage into barrier is counted as damage
-            if event.result == 8 || event.result == 9 || event.result == 10 {
-                // Killing blow (8) or enemy downed (9) or breakbar damage (10)
+            if let CbtResult::KillingBlow | CbtResult::Downed | CbtResult::BreakbarDamage = event.hit_result() {
                 continue;
             }
             let skill_id = event.skill_id;
             let damage = event.value;
-            let crit = event.result == 1;
+            let flanking = event.is_flanking != 0;
 
-            assert_ne!(event.src_master_inst_id, player_inst_id); // minion damage, not implemented
-
-            if event.src_agent != player.address || event.dst_agent != target.address {
+            if event.dst_agent != target.address {
                 continue;
             }
+            match sources.source(event) {
+                EventSource::Player | EventSource::Minion(MinionAttributes::Owner) => {}
+                EventSource::Minion(MinionAttributes::Own) => {
+                    simulation_events.push(MinionDamage { time: event.time, skill_id, damage: damage.max(0) as u64 });
+                    continue;
+                }
+                EventSource::Other => continue,
+            }
+
+            let result = match event.hit_result() {
+                // The log only has one result, an interrupting hit is treated as a non-critical one
+                CbtResult::Normal | CbtResult::Interrupt => HitResult::Normal,
+                CbtResult::Critical => HitResult::Critical,
+                CbtResult::Glance => HitResult::Glance,
+                CbtResult::Block | CbtResult::Evade | CbtResult::Absorb | CbtResult::Blind => HitResult::Missed,
+                other => {
+                    if !warned_unknown_hit_result {
+                        eprintln!("Warning, skipping physical hits with unexpected result {:?}", other);
+                        warned_unknown_hit_result = true;
+                    }
+                    continue;
+                }
+            };
 
-            assert_ne!(event.result, 2); // glance
-            assert_ne!(event.result, 3); // block
-            assert_ne!(event.result, 4); // evade
-            assert_ne!(event.result, 5); // interrupt of enemy
-            assert_ne!(event.result, 6); // absorbed
-            assert_ne!(event.result, 7); // blind - miss
+            let target_armor = target.toughness as u32 + gamedata.rules.base_enemy_armor;
+            let source = if skill_id == ids::skills::RING_OF_EARTH {
+                PhysicalHitSource::Sigil { sigil: Sigil::Geomancy, skill_id, weapon_set: stats.weapon_set }
+            } else {
+                PhysicalHitSource::Skill(skill_id)
+            };
 
+            if result == HitResult::Missed {
+                // Nothing to reverse, the hit only matters for on-hit effects
+                simulation_events.push(PhysicalHit { time: event.time, base_damage: 0, coefficient: 0., source, enemy_armor: target_armor, result, flanking });
+                continue;
+            }
 
             let skill_multiplier = match skill_id {
                 ids::skills::SEARING_FISSURE => {
@@ -266,9 +419,9 @@ pub fn get_events(evtc_log: &EvtcLog, player: &EvtcAgent, target: &EvtcAgent, mu
                             && event.skill_id == ids::skills::BURNING {
                             let duration = event.value as u32;
                             let base_duration = get_base_duration(&mut stats, event.skill_id, duration, event.time);
-                            if (base_duration as i64 - gamedata::SEARING_FISSURE_FIRST_STRIKE_BURNING_DURATION as i64).abs() < 5 {
+                            if (base_duration as i64 - gamedata.rules.searing_fissure_first_strike_burning_duration as i64).abs() < 5 {
                                 first_burnings += 1;
-                            } else if (base_duration as i64 - gamedata::SEARING_FISSURE_ADDITIONAL_STRIKE_BURNING_DURATION as i64).abs() < 5 {
+                            } else if (base_duration as i64 - gamedata.rules.searing_fissure_additional_strike_burning_duration as i64).abs() < 5 {
                                 additional_burnings += 1;
                             }
                         }
@@ -277,31 +430,30 @@ pub fn get_events(evtc_log: &EvtcLog, player: &EvtcAgent, target: &EvtcAgent, mu
                     // This is likely also doable by checking for skill cast events
                     // in case this is ever unreliable.
                     if first_burnings == 3 && additional_burnings == 0 {
-                        gamedata::SEARING_FISSURE_FIRST_STRIKE_MULTIPLIER
+                        gamedata.rules.searing_fissure_first_strike_multiplier
                     } else if first_burnings == 0 && additional_burnings == 1 {
-                        gamedata::SEARING_FISSURE_ADDITIONAL_STRIKE_MULTIPLIER
+                        gamedata.rules.searing_fissure_additional_strike_multiplier
                     } else if first_burnings >= 3 {
                         // May be a false positive in case many skills with the same base duration
                         // land at the same time.
                         eprintln!("Warning, unsure about Searing Fissure type: {} burn matching first burn, {} burn matching additional burn, guessing first strike", first_burnings, additional_burnings);
-                        gamedata::SEARING_FISSURE_FIRST_STRIKE_MULTIPLIER
+                        gamedata.rules.searing_fissure_first_strike_multiplier
                     } else {
                         // First burnings < 3, very unlikely to be first strike
                         eprintln!("Warning, unsure about Searing Fissure type: {} burn matching first burn, {} burn matching additional burn, guessing additional strike", first_burnings, additional_burnings);
-                        gamedata::SEARING_FISSURE_ADDITIONAL_STRIKE_MULTIPLIER
+                        gamedata.rules.searing_fissure_additional_strike_multiplier
                     }
                 }
-                _ => gamedata.power_multiplier(skill_id).expect("Failed to find skill multiplier")
+                _ => gamedata.skills.power_multiplier(skill_id).expect("Failed to find skill multiplier")
             };
 
-            let target_armor = target.toughness as u32 + gamedata::BASE_ENEMY_ARMOR as u32;
             let mut base_damage = damage as f64 / stats.power(event.time) as f64 / skill_multiplier * target_armor as f64;
-            if crit {
-                let crit_damage = 1.5 + stats.ferocity(event.time) as f64 / 1500.;
-                base_damage /= crit_damage;
+            match result {
+                HitResult::Critical => base_damage /= stats.critical_damage(event.time),
+                HitResult::Glance => base_damage /= stats::GLANCE_DAMAGE_MULTIPLIER,
+                HitResult::Normal | HitResult::Missed => {}
             }
-            base_damage /= 1. + target_buffs.get_stack_count(ids::skills::VULNERABILITY, event.time) as f64 * 0.01;
-            base_damage /= stats.power_damage_mult(event.time, &mut target_buffs, target_health);
+            base_damage /= stats.damage_mult(DamageType::Strike, event.time, &mut target_buffs, target_health, flanking);
             //eprintln!("{}->{} | skill {} | PWR {} | CRIT {} | FERO {} |", base_damage, damage, skill_id, stats.power(event.time), crit, stats.ferocity(event.time))
 
 
@@ -309,19 +461,26 @@ pub fn get_events(evtc_log: &EvtcLog, player: &EvtcAgent, target: &EvtcAgent, mu
                 time: event.time,
                 base_damage: base_damage as u32,
                 coefficient: skill_multiplier,
-                source: PhysicalHitSource::Skill(skill_id),
+                source,
                 enemy_armor: target_armor,
-                critical: crit,
+                result,
+                flanking,
             });
         } else if event.is_buff > 0 && event.value == 0 && event.is_state_change == 0 && event.is_activation == 0 && event.is_buff_remove == 0 {
             // Buff damage
-            let damage = event.buff_dmg;
             if event.result != 0 {
                 // Damage did not hit.
                 continue;
             }
-            if event.src_agent != player.address {
-                continue;
+            match sources.source(event) {
+                EventSource::Player | EventSource::Minion(MinionAttributes::Owner) => {}
+                EventSource::Minion(MinionAttributes::Own) => {
+                    if event.dst_agent == target.address {
+                        simulation_events.push(MinionDamage { time: event.time, skill_id: event.skill_id, damage: event.buff_dmg.max(0) as u64 });
+                    }
+                    continue;
+                }
+                EventSource::Other => continue,
             }
 
             if event.is_offcycle > 0 {
@@ -329,15 +488,16 @@ pub fn get_events(evtc_log: &EvtcLog, player: &EvtcAgent, target: &EvtcAgent, mu
                 if event.skill_id == ids::skills::BATTLE_SCARS {
                     simulation_events.push(SimulationEvent::LifeStealHit {
                         time: event.time,
-                        base_damage: gamedata::BATTLE_SCARS_DAMAGE,
-                        power_scaling: gamedata::BATTLE_SCARS_MULTIPLIER,
+                        base_damage: gamedata.rules.battle_scars.base_damage,
+                        power_scaling: gamedata.rules.battle_scars.multiplier,
                         source: LifeStealSource::Buff(ids::skills::BATTLE_SCARS),
                     });
                 } else {
                     unimplemented!();
                 }
             } else {
-                let damaging_condition = DamagingCondition::from_id(event.skill_id);
+                // Panics on conditions that are not supported
+                DamagingCondition::from_id(event.skill_id);
                 let dst_moving = (event.is_moving & 0b10) >> 1 == 1;
 
                 if event.time - last_condition_tick > 5 {
@@ -352,4 +512,262 @@ pub fn get_events(evtc_log: &EvtcLog, player: &EvtcAgent, target: &EvtcAgent, mu
     }
 
     simulation_events
-}
\ No newline at end of file
+}
+/// Damage of the player and their minions to the target as recorded in the log, grouped the same way as the simulation.
+/// Condition damage of one tick is grouped at the time of the first condition of the tick,
+/// damage of minions with their own attributes is kept at its own time like `MinionDamage` events.
+pub fn logged_damage(evtc_log: &EvtcLog, player: &EvtcAgent, target: &EvtcAgent) -> DamageDistribution {
+    let mut damage_distribution = DamageDistribution::new();
+    let sources = PlayerSources::new(evtc_log, player);
+    let mut last_condition_tick = 0;
+    let mut tick_time = 0;
+    for event in evtc_log.combat_items.iter().sorted_by_key(|x| x.time) {
+        if sources.source(event) == EventSource::Other || event.dst_agent != target.address {
+            continue;
+        }
+
+        if event.is_physical_hit() {
+            if let CbtResult::KillingBlow | CbtResult::Downed | CbtResult::BreakbarDamage = event.hit_result() {
+                continue;
+            }
+            damage_distribution.add_damage_at(event.time, event.skill_id, event.value.max(0) as u64);
+        } else if event.is_buff > 0 && event.value == 0 && event.is_state_change == 0 && event.is_activation == 0 && event.is_buff_remove == 0 {
+            if event.result != 0 {
+                continue;
+            }
+            let own_attributes_minion = sources.source(event) == EventSource::Minion(MinionAttributes::Own);
+            if event.is_offcycle > 0 || own_attributes_minion {
+                damage_distribution.add_damage_at(event.time, event.skill_id, event.buff_dmg.max(0) as u64);
+            } else {
+                if event.time - last_condition_tick > 5 {
+                    tick_time = event.time;
+                }
+                last_condition_tick = event.time;
+                damage_distribution.add_damage_at(tick_time, event.skill_id, event.buff_dmg.max(0) as u64);
+            }
+        }
+    }
+
+    damage_distribution
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::OnceLock;
+    use crate::build_file::BuildFile;
+    use crate::evtc::LogMetadata;
+    use crate::gamedata::GamedataRegistry;
+    use crate::sim::{self, CritModel, OriginalProcs, SimBuffUptimes};
+
+    const PLAYER: u64 = 1;
+    const TARGET: u64 = 2;
+    const ALLY: u64 = 3;
+    /// Visk Icerazor of the player
+    const SPIRIT: u64 = 4;
+    /// A minion of the player that is not listed in the gamedata
+    const OTHER_MINION: u64 = 5;
+    const ICERAZORS_IRE: u32 = 43856;
+
+    /// The original build and the gamedata, loaded once for all tests.
+    struct Setup {
+        original: BuildFile,
+        registry: GamedataRegistry,
+    }
+
+    fn setup() -> &'static Setup {
+        static SETUP: OnceLock<Setup> = OnceLock::new();
+        SETUP.get_or_init(|| Setup {
+            original: BuildFile::from_file("builds/renegade-original.toml").unwrap(),
+            registry: GamedataRegistry::load("gamedata").unwrap(),
+        })
+    }
+
+    fn agent(address: u64, profession: u32, is_elite: u32) -> EvtcAgent {
+        EvtcAgent {
+            address,
+            profession,
+            is_elite,
+            toughness: 0,
+            concentration: 0,
+            healing: 0,
+            condition: 0,
+            hitbox_width: 0,
+            hitbox_height: 0,
+            name: String::new(),
+            character_name: String::new(),
+            account_name: None,
+            subgroup: None,
+        }
+    }
+
+    fn item(time: i64, src_agent: u64, dst_agent: u64) -> EvtcCombatItem {
+        EvtcCombatItem {
+            time,
+            src_agent,
+            dst_agent,
+            value: 0,
+            buff_dmg: 0,
+            overstack_value: 0,
+            skill_id: 0,
+            src_inst_id: if src_agent == PLAYER { 10 } else { 0 },
+            dst_inst_id: 0,
+            src_master_inst_id: if src_agent == SPIRIT || src_agent == OTHER_MINION { 10 } else { 0 },
+            dst_master_inst_id: 0,
+            iff: 0,
+            is_buff: 0,
+            result: 0,
+            is_activation: 0,
+            is_buff_remove: 0,
+            is_ninety: 0,
+            is_fifty: 0,
+            is_moving: 0,
+            is_state_change: 0,
+            is_flanking: 0,
+            is_shields: 0,
+            is_offcycle: 0,
+            padding: 0,
+        }
+    }
+
+    fn buff_apply(time: i64, skill_id: u32, src_agent: u64, dst_agent: u64, duration: i32) -> EvtcCombatItem {
+        EvtcCombatItem { skill_id, value: duration, is_buff: 1, ..item(time, src_agent, dst_agent) }
+    }
+
+    /// Removal of a single stack from `owner` that was applied by `stack_source`.
+    fn buff_remove(time: i64, skill_id: u32, owner: u64, stack_source: u64, remaining_duration: i32) -> EvtcCombatItem {
+        EvtcCombatItem { skill_id, value: remaining_duration, is_buff: 1, is_buff_remove: 2, ..item(time, owner, stack_source) }
+    }
+
+    fn hit(time: i64, src_agent: u64, damage: i32, result: CbtResult) -> EvtcCombatItem {
+        EvtcCombatItem { skill_id: ICERAZORS_IRE, value: damage, result: result.into(), ..item(time, src_agent, TARGET) }
+    }
+
+    fn condition_damage(time: i64, skill_id: u32, damage: i32) -> EvtcCombatItem {
+        EvtcCombatItem { skill_id, buff_dmg: damage, is_buff: 1, ..item(time, PLAYER, TARGET) }
+    }
+
+    /// A Renegade player, an ally, two minions of the player and a target with one million health.
+    fn log(combat_items: Vec<EvtcCombatItem>) -> EvtcLog {
+        let mut all_items = vec![
+            EvtcCombatItem { is_state_change: 12, ..item(0, TARGET, 1_000_000) },
+            // Any non state change event of the player is needed to find its instance id
+            EvtcCombatItem { is_activation: 1, ..item(0, PLAYER, TARGET) },
+        ];
+        all_items.extend(combat_items);
+        EvtcLog {
+            version: String::new(),
+            revision: 1,
+            boss_species_id: 1,
+            metadata: LogMetadata::default(),
+            agents: vec![
+                agent(PLAYER, 9, 63),
+                agent(TARGET, 1, 0xff_ff_ff_ff),
+                agent(ALLY, 9, 63),
+                agent(SPIRIT, 18524, 0xff_ff_ff_ff),
+                agent(OTHER_MINION, 1, 0xff_ff_ff_ff),
+            ],
+            skills: Vec::new(),
+            combat_items: all_items,
+        }
+    }
+
+    fn extract(combat_items: Vec<EvtcCombatItem>) -> Vec<SimulationEvent> {
+        let evtc_log = log(combat_items);
+        let stats = setup().original.to_stats(LogBuffUptimes::new());
+        get_events(&evtc_log, &evtc_log.agents[0], &evtc_log.agents[1], stats, setup().registry.latest())
+    }
+
+    fn removals(combat_items: Vec<EvtcCombatItem>) -> Vec<(i64, u32, BuffTarget, bool)> {
+        extract(combat_items).into_iter()
+            .filter_map(|x| match x {
+                BuffRemoval { time, skill_id, target, all_stacks } => Some((time, skill_id, target, all_stacks)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Resimulates the events with `build` in place of the original build.
+    fn resimulate(events: &[SimulationEvent], build: &BuildFile) -> DamageDistribution {
+        let original = &setup().original;
+        let original_procs = OriginalProcs { sigils: original.sigils(), rune_procs: original.rune_set().procs };
+        let evtc_log = log(Vec::new());
+        let enemy_max_health = evtc_log.max_health_history(&evtc_log.agents[1]);
+        sim::sim(build.to_stats(SimBuffUptimes::new()), events, setup().registry.latest(), &enemy_max_health, &original_procs, CritModel::Log)
+    }
+
+    #[test]
+    fn removals_within_the_expiry_tolerance_are_natural_expiry() {
+        let removals = removals(vec![
+            buff_apply(100, ids::skills::FURY, PLAYER, PLAYER, 5000),
+            buff_remove(5100 - EXPIRY_TOLERANCE as i64, ids::skills::FURY, PLAYER, PLAYER, EXPIRY_TOLERANCE),
+            buff_apply(6000, ids::skills::FURY, PLAYER, PLAYER, 5000),
+            buff_remove(7000, ids::skills::FURY, PLAYER, PLAYER, 4000),
+        ]);
+        assert_eq!(removals, vec![(7000, ids::skills::FURY, BuffTarget::Player, false)]);
+    }
+
+    #[test]
+    fn expiring_boons_of_allies_are_not_removed_from_the_player() {
+        let removals = removals(vec![
+            buff_apply(1000, ids::skills::MIGHT, ALLY, PLAYER, 5000),
+            buff_remove(5000, ids::skills::MIGHT, PLAYER, ALLY, 1000),
+            buff_apply(6000, ids::skills::MIGHT, PLAYER, PLAYER, 5000),
+            buff_remove(7000, ids::skills::MIGHT, PLAYER, PLAYER, 4000),
+        ]);
+        assert_eq!(removals, vec![(7000, ids::skills::MIGHT, BuffTarget::Player, false)]);
+    }
+
+    #[test]
+    fn target_buff_removals_of_other_sources_are_ignored() {
+        let removals = removals(vec![
+            buff_apply(1000, ids::skills::VULNERABILITY, PLAYER, TARGET, 10000),
+            buff_apply(1000, ids::skills::VULNERABILITY, ALLY, TARGET, 5000),
+            buff_remove(3000, ids::skills::VULNERABILITY, TARGET, ALLY, 3000),
+            buff_remove(4000, ids::skills::VULNERABILITY, TARGET, PLAYER, 7000),
+        ]);
+        assert_eq!(removals, vec![(4000, ids::skills::VULNERABILITY, BuffTarget::Target, false)]);
+    }
+
+    #[test]
+    fn listed_minions_are_resimulated_with_player_stats() {
+        let events = extract(vec![
+            hit(1000, SPIRIT, 1000, CbtResult::Normal),
+            hit(2000, OTHER_MINION, 500, CbtResult::Normal),
+        ]);
+        assert!(events.iter().any(|x| matches!(x,
+            PhysicalHit { time: 1000, source: PhysicalHitSource::Skill(ICERAZORS_IRE), result: HitResult::Normal, .. })));
+        assert!(events.iter().any(|x| matches!(x, MinionDamage { time: 2000, skill_id: ICERAZORS_IRE, damage: 500 })));
+        assert!(!events.iter().any(|x| matches!(x, MinionDamage { time: 1000, .. } | PhysicalHit { time: 2000, .. })));
+    }
+
+    #[test]
+    fn glancing_hits_are_resimulated_at_their_logged_damage() {
+        let events = extract(vec![hit(1000, PLAYER, 1000, CbtResult::Glance)]);
+        assert!(events.iter().any(|x| matches!(x, PhysicalHit { time: 1000, result: HitResult::Glance, .. })));
+
+        let damage = resimulate(&events, &setup().original).damage_by_event[&(1000, ICERAZORS_IRE)];
+        assert!((damage as i64 - 1000).abs() <= 1, "Glance resimulated as {}", damage);
+    }
+
+    #[test]
+    fn hits_with_unexpected_results_are_skipped() {
+        let events = extract(vec![hit(1000, PLAYER, 0, CbtResult::Activation)]);
+        assert!(!events.iter().any(|x| matches!(x, PhysicalHit { .. })));
+    }
+
+    #[test]
+    fn missed_hits_trigger_on_hit_procs() {
+        let events = extract(vec![
+            hit(1000, PLAYER, 0, CbtResult::Block),
+            // Only the tick time of the logged poison damage is used
+            condition_damage(2000, ids::skills::POISONED, 100),
+        ]);
+        assert!(events.iter().any(|x| matches!(x, PhysicalHit { time: 1000, result: HitResult::Missed, .. })));
+        assert!(!resimulate(&events, &setup().original).damage_by_skill.contains_key(&ids::skills::POISONED));
+
+        // The Doom rune poisons on hit
+        let doom = BuildFile { runes: "Doom".to_string(), ..setup().original.clone() };
+        assert!(resimulate(&events, &doom).damage_by_skill[&ids::skills::POISONED] > 0);
+    }
+}
@@ -0,0 +1,275 @@
+use crate::{ids, stats};
+use crate::gamedata::{BuffStackingType, SkillType};
+use crate::modifiers::{DamageModifier, DamageTypes, ModifierBucket, ModifierRequirement, ModifierScaling};
+
+/// Intensity stacks of damaging conditions, shared between all sources.
+const CONDITION_INTENSITY_LIMIT: u32 = 1500;
+
+/// Who can apply stacks of a buff.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum BuffSources {
+    /// Stacks from all sources count towards the same stack limit.
+    Shared,
+    /// Only the bearer applies the buff to themselves, for example from traits or sigils.
+    SelfOnly,
+}
+
+pub struct BuffDefinition {
+    pub name: &'static str,
+    pub skill_id: u32,
+    pub skill_type: SkillType,
+    pub stacking: BuffStackingType,
+    /// Maximum of stacks, for duration stacking this includes the queued stacks.
+    pub stack_limit: u32,
+    pub sources: BuffSources,
+}
+
+// Food and utility buffs are not tracked, their attributes and condition duration come from the build file instead
+pub const BUFFS: &[BuffDefinition] = &[
+    BuffDefinition {
+        name: "Protection",
+        skill_id: ids::skills::PROTECTION,
+        skill_type: SkillType::Boon,
+        stacking: BuffStackingType::Duration,
+        stack_limit: 9,
+        sources: BuffSources::Shared,
+    },
+    BuffDefinition {
+        name: "Regeneration",
+        skill_id: ids::skills::REGENERATION,
+        skill_type: SkillType::Boon,
+        stacking: BuffStackingType::Duration,
+        stack_limit: 9,
+        sources: BuffSources::Shared,
+    },
+    BuffDefinition {
+        name: "Swiftness",
+        skill_id: ids::skills::SWIFTNESS,
+        skill_type: SkillType::Boon,
+        stacking: BuffStackingType::Duration,
+        stack_limit: 9,
+        sources: BuffSources::Shared,
+    },
+    BuffDefinition {
+        name: "Fury",
+        skill_id: ids::skills::FURY,
+        skill_type: SkillType::Boon,
+        stacking: BuffStackingType::Duration,
+        stack_limit: 9,
+        sources: BuffSources::Shared,
+    },
+    BuffDefinition {
+        name: "Vigor",
+        skill_id: ids::skills::VIGOR,
+        skill_type: SkillType::Boon,
+        stacking: BuffStackingType::Duration,
+        stack_limit: 9,
+        sources: BuffSources::Shared,
+    },
+    BuffDefinition {
+        name: "Might",
+        skill_id: ids::skills::MIGHT,
+        skill_type: SkillType::Boon,
+        stacking: BuffStackingType::Intensity,
+        stack_limit: 25,
+        sources: BuffSources::Shared,
+    },
+    BuffDefinition {
+        name: "Aegis",
+        skill_id: ids::skills::AEGIS,
+        skill_type: SkillType::Boon,
+        stacking: BuffStackingType::Duration,
+        stack_limit: 9,
+        sources: BuffSources::Shared,
+    },
+    BuffDefinition {
+        name: "Resolution",
+        skill_id: ids::skills::RESOLUTION,
+        skill_type: SkillType::Boon,
+        stacking: BuffStackingType::Duration,
+        stack_limit: 9,
+        sources: BuffSources::Shared,
+    },
+    BuffDefinition {
+        name: "Stability",
+        skill_id: ids::skills::STABILITY,
+        skill_type: SkillType::Boon,
+        stacking: BuffStackingType::Intensity,
+        stack_limit: 25,
+        sources: BuffSources::Shared,
+    },
+    BuffDefinition {
+        name: "Quickness",
+        skill_id: ids::skills::QUICKNESS,
+        skill_type: SkillType::Boon,
+        stacking: BuffStackingType::Duration,
+        stack_limit: 9,
+        sources: BuffSources::Shared,
+    },
+    BuffDefinition {
+        name: "Resistance",
+        skill_id: ids::skills::RESISTANCE,
+        skill_type: SkillType::Boon,
+        stacking: BuffStackingType::Duration,
+        stack_limit: 9,
+        sources: BuffSources::Shared,
+    },
+    BuffDefinition {
+        name: "Alacrity",
+        skill_id: ids::skills::ALACRITY,
+        skill_type: SkillType::Boon,
+        stacking: BuffStackingType::Duration,
+        stack_limit: 9,
+        sources: BuffSources::Shared,
+    },
+    BuffDefinition {
+        name: "Bleeding",
+        skill_id: ids::skills::BLEEDING,
+        skill_type: SkillType::Condition,
+        stacking: BuffStackingType::Intensity,
+        stack_limit: CONDITION_INTENSITY_LIMIT,
+        sources: BuffSources::Shared,
+    },
+    BuffDefinition {
+        name: "Burning",
+        skill_id: ids::skills::BURNING,
+        skill_type: SkillType::Condition,
+        stacking: BuffStackingType::Intensity,
+        stack_limit: CONDITION_INTENSITY_LIMIT,
+        sources: BuffSources::Shared,
+    },
+    BuffDefinition {
+        name: "Confusion",
+        skill_id: ids::skills::CONFUSION,
+        skill_type: SkillType::Condition,
+        stacking: BuffStackingType::Intensity,
+        stack_limit: CONDITION_INTENSITY_LIMIT,
+        sources: BuffSources::Shared,
+    },
+    BuffDefinition {
+        name: "Poisoned",
+        skill_id: ids::skills::POISONED,
+        skill_type: SkillType::Condition,
+        stacking: BuffStackingType::Intensity,
+        stack_limit: CONDITION_INTENSITY_LIMIT,
+        sources: BuffSources::Shared,
+    },
+    BuffDefinition {
+        name: "Torment",
+        skill_id: ids::skills::TORMENT,
+        skill_type: SkillType::Condition,
+        stacking: BuffStackingType::Intensity,
+        stack_limit: CONDITION_INTENSITY_LIMIT,
+        sources: BuffSources::Shared,
+    },
+    BuffDefinition {
+        name: "Vulnerability",
+        skill_id: ids::skills::VULNERABILITY,
+        skill_type: SkillType::Condition,
+        stacking: BuffStackingType::Intensity,
+        stack_limit: 25,
+        sources: BuffSources::Shared,
+    },
+    BuffDefinition {
+        name: "Blinded",
+        skill_id: ids::skills::BLINDED,
+        skill_type: SkillType::Condition,
+        stacking: BuffStackingType::Duration,
+        stack_limit: 9,
+        sources: BuffSources::Shared,
+    },
+    BuffDefinition {
+        name: "Crippled",
+        skill_id: ids::skills::CRIPPLED,
+        skill_type: SkillType::Condition,
+        stacking: BuffStackingType::Duration,
+        stack_limit: 9,
+        sources: BuffSources::Shared,
+    },
+    BuffDefinition {
+        name: "Chilled",
+        skill_id: ids::skills::CHILLED,
+        skill_type: SkillType::Condition,
+        stacking: BuffStackingType::Duration,
+        stack_limit: 9,
+        sources: BuffSources::Shared,
+    },
+    BuffDefinition {
+        name: "Immobile",
+        skill_id: ids::skills::IMMOBILE,
+        skill_type: SkillType::Condition,
+        stacking: BuffStackingType::Duration,
+        stack_limit: 9,
+        sources: BuffSources::Shared,
+    },
+    BuffDefinition {
+        name: "Weakness",
+        skill_id: ids::skills::WEAKNESS,
+        skill_type: SkillType::Condition,
+        stacking: BuffStackingType::Duration,
+        stack_limit: 9,
+        sources: BuffSources::Shared,
+    },
+    BuffDefinition {
+        name: "Slow",
+        skill_id: ids::skills::SLOW,
+        skill_type: SkillType::Condition,
+        stacking: BuffStackingType::Duration,
+        stack_limit: 9,
+        sources: BuffSources::Shared,
+    },
+    BuffDefinition {
+        name: "Fear",
+        skill_id: ids::skills::FEAR,
+        skill_type: SkillType::Condition,
+        stacking: BuffStackingType::Duration,
+        stack_limit: 9,
+        sources: BuffSources::Shared,
+    },
+    BuffDefinition {
+        name: "Taunt",
+        skill_id: ids::skills::TAUNT,
+        skill_type: SkillType::Condition,
+        stacking: BuffStackingType::Duration,
+        stack_limit: 9,
+        sources: BuffSources::Shared,
+    },
+    BuffDefinition {
+        name: "Kalla's Fervor",
+        skill_id: ids::skills::KALLAS_FERVOR,
+        skill_type: SkillType::GenericBuff,
+        stacking: BuffStackingType::Intensity,
+        stack_limit: 5,
+        sources: BuffSources::SelfOnly,
+    },
+    BuffDefinition {
+        name: "Doom",
+        skill_id: ids::skills::DOOM,
+        skill_type: SkillType::GenericBuff,
+        stacking: BuffStackingType::Duration,
+        stack_limit: 1,
+        sources: BuffSources::SelfOnly,
+    },
+];
+
+pub fn definition(skill_id: u32) -> Option<&'static BuffDefinition> {
+    BUFFS.iter().find(|x| x.skill_id == skill_id)
+}
+
+/// Damage modifiers of buffs on the player and debuffs on the target, they apply to every build.
+pub const DAMAGE_MODIFIERS: &[DamageModifier] = &[
+    DamageModifier {
+        bucket: ModifierBucket::Multiplicative,
+        damage_types: DamageTypes::STRIKE_AND_CONDITIONS,
+        requirement: ModifierRequirement::Always,
+        scaling: ModifierScaling::PerTargetStack(ids::skills::VULNERABILITY),
+        value: 0.01,
+    },
+    DamageModifier {
+        bucket: ModifierBucket::Additive,
+        damage_types: DamageTypes::ALL,
+        requirement: ModifierRequirement::Always,
+        scaling: ModifierScaling::PerPlayerStack(ids::skills::KALLAS_FERVOR),
+        value: stats::KALLAS_FERVOR_CONDITION_DAMAGE_MULTIPLIER,
+    },
+];
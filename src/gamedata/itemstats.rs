@@ -0,0 +1,148 @@
+use serde::Deserialize;
+use crate::build_file::Attributes;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize)]
+pub enum Rarity {
+    Exotic,
+    Ascended,
+    /// Same attributes as ascended
+    Legendary,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Slot {
+    Helm,
+    Shoulders,
+    Coat,
+    Gloves,
+    Leggings,
+    Boots,
+    Back,
+    Accessory,
+    Amulet,
+    Ring,
+    OneHandedWeapon,
+    TwoHandedWeapon,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Attribute {
+    Power,
+    Precision,
+    Toughness,
+    Vitality,
+    Ferocity,
+    ConditionDamage,
+    Expertise,
+    Concentration,
+    HealingPower,
+}
+
+/// A combination of attributes that can be selected on gear, named by its prefix.
+pub struct Itemstat {
+    pub name: &'static str,
+    pub major: &'static [Attribute],
+    pub minor: &'static [Attribute],
+}
+
+use Attribute::*;
+
+/// Itemstat combinations with three or four attributes, Celestial uses different values and is not included.
+pub const ITEMSTATS: &[Itemstat] = &[
+    Itemstat { name: "Berserker", major: &[Power], minor: &[Precision, Ferocity] },
+    Itemstat { name: "Assassin", major: &[Precision], minor: &[Power, Ferocity] },
+    Itemstat { name: "Valkyrie", major: &[Power], minor: &[Vitality, Ferocity] },
+    Itemstat { name: "Soldier", major: &[Power], minor: &[Toughness, Vitality] },
+    Itemstat { name: "Rampager", major: &[Precision], minor: &[Power, ConditionDamage] },
+    Itemstat { name: "Sinister", major: &[ConditionDamage], minor: &[Power, Precision] },
+    Itemstat { name: "Carrion", major: &[ConditionDamage], minor: &[Power, Vitality] },
+    Itemstat { name: "Rabid", major: &[ConditionDamage], minor: &[Precision, Toughness] },
+    Itemstat { name: "Dire", major: &[ConditionDamage], minor: &[Toughness, Vitality] },
+    Itemstat { name: "Viper", major: &[Power, ConditionDamage], minor: &[Precision, Expertise] },
+    Itemstat { name: "Grieving", major: &[Power, ConditionDamage], minor: &[Precision, Ferocity] },
+    Itemstat { name: "Marauder", major: &[Power, Precision], minor: &[Vitality, Ferocity] },
+    Itemstat { name: "Dragon", major: &[Power, Ferocity], minor: &[Precision, Vitality] },
+    Itemstat { name: "Diviner", major: &[Power, Concentration], minor: &[Precision, Ferocity] },
+    Itemstat { name: "Seraph", major: &[Precision, ConditionDamage], minor: &[Concentration, HealingPower] },
+    Itemstat { name: "Trailblazer", major: &[Toughness, ConditionDamage], minor: &[Vitality, Expertise] },
+];
+
+pub fn by_name(name: &str) -> Option<&'static Itemstat> {
+    ITEMSTATS.iter().find(|x| x.name == name)
+}
+
+/// Values of a single major and minor attribute for a slot, depends on the amount of attributes of the itemstat.
+pub fn slot_values(slot: Slot, rarity: Rarity, attribute_count: usize) -> Option<(u32, u32)> {
+    let values = match (rarity, attribute_count, slot) {
+        (Rarity::Ascended | Rarity::Legendary, 3, Slot::Helm) => (63, 45),
+        (Rarity::Ascended | Rarity::Legendary, 3, Slot::Shoulders) => (47, 34),
+        (Rarity::Ascended | Rarity::Legendary, 3, Slot::Coat) => (141, 101),
+        (Rarity::Ascended | Rarity::Legendary, 3, Slot::Gloves) => (47, 34),
+        (Rarity::Ascended | Rarity::Legendary, 3, Slot::Leggings) => (94, 67),
+        (Rarity::Ascended | Rarity::Legendary, 3, Slot::Boots) => (47, 34),
+        (Rarity::Ascended | Rarity::Legendary, 3, Slot::Back) => (63, 40),
+        (Rarity::Ascended | Rarity::Legendary, 3, Slot::Accessory) => (110, 74),
+        (Rarity::Ascended | Rarity::Legendary, 3, Slot::Amulet) => (157, 108),
+        (Rarity::Ascended | Rarity::Legendary, 3, Slot::Ring) => (126, 85),
+        (Rarity::Ascended | Rarity::Legendary, 3, Slot::OneHandedWeapon) => (125, 90),
+        (Rarity::Ascended | Rarity::Legendary, 3, Slot::TwoHandedWeapon) => (251, 179),
+
+        (Rarity::Ascended | Rarity::Legendary, 4, Slot::Helm) => (54, 30),
+        (Rarity::Ascended | Rarity::Legendary, 4, Slot::Shoulders) => (40, 22),
+        (Rarity::Ascended | Rarity::Legendary, 4, Slot::Coat) => (121, 67),
+        (Rarity::Ascended | Rarity::Legendary, 4, Slot::Gloves) => (40, 22),
+        (Rarity::Ascended | Rarity::Legendary, 4, Slot::Leggings) => (81, 44),
+        (Rarity::Ascended | Rarity::Legendary, 4, Slot::Boots) => (40, 22),
+        (Rarity::Ascended | Rarity::Legendary, 4, Slot::Back) => (52, 27),
+        (Rarity::Ascended | Rarity::Legendary, 4, Slot::Accessory) => (92, 49),
+        (Rarity::Ascended | Rarity::Legendary, 4, Slot::Amulet) => (133, 71),
+        (Rarity::Ascended | Rarity::Legendary, 4, Slot::Ring) => (106, 56),
+        (Rarity::Ascended | Rarity::Legendary, 4, Slot::OneHandedWeapon) => (108, 59),
+        (Rarity::Ascended | Rarity::Legendary, 4, Slot::TwoHandedWeapon) => (215, 118),
+
+        (Rarity::Exotic, 3, Slot::Helm) => (60, 43),
+        (Rarity::Exotic, 3, Slot::Shoulders) => (45, 32),
+        (Rarity::Exotic, 3, Slot::Coat) => (134, 96),
+        (Rarity::Exotic, 3, Slot::Gloves) => (45, 32),
+        (Rarity::Exotic, 3, Slot::Leggings) => (90, 64),
+        (Rarity::Exotic, 3, Slot::Boots) => (45, 32),
+        (Rarity::Exotic, 3, Slot::OneHandedWeapon) => (120, 85),
+        (Rarity::Exotic, 3, Slot::TwoHandedWeapon) => (239, 171),
+
+        (Rarity::Exotic, 4, Slot::Helm) => (51, 28),
+        (Rarity::Exotic, 4, Slot::Shoulders) => (38, 21),
+        (Rarity::Exotic, 4, Slot::Coat) => (115, 63),
+        (Rarity::Exotic, 4, Slot::Gloves) => (38, 21),
+        (Rarity::Exotic, 4, Slot::Leggings) => (77, 42),
+        (Rarity::Exotic, 4, Slot::Boots) => (38, 21),
+
+        // TODO: Exotic trinkets and exotic 4-stat weapons
+        _ => return None,
+    };
+    Some(values)
+}
+
+impl Itemstat {
+    /// Attributes of an item with this itemstat, None if the values for the slot and rarity are not known.
+    ///
+    /// Toughness, vitality and healing power are not modelled and are left out.
+    pub fn attributes(&self, slot: Slot, rarity: Rarity) -> Option<Attributes> {
+        let (major_value, minor_value) = slot_values(slot, rarity, self.major.len() + self.minor.len())?;
+
+        let mut attributes = Attributes::default();
+        let values = self.major.iter().map(|&x| (x, major_value))
+            .chain(self.minor.iter().map(|&x| (x, minor_value)));
+        for (attribute, value) in values {
+            match attribute {
+                Power => attributes.power += value,
+                Precision => attributes.precision += value,
+                Ferocity => attributes.ferocity += value,
+                ConditionDamage => attributes.condition_damage += value,
+                Expertise => attributes.expertise += value,
+                Concentration => attributes.concentration += value,
+                Toughness | Vitality | HealingPower => {}
+            }
+        }
+        Some(attributes)
+    }
+}
@@ -0,0 +1,54 @@
+/// Whose attributes scale the damage of a minion, pet, spirit or clone.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MinionAttributes {
+    /// Strikes and conditions scale with the attributes and modifiers of the owning player,
+    /// they are resimulated the same way as the player's own damage.
+    Owner,
+    /// The minion has attributes of its own that do not depend on the gear of the player,
+    /// its damage is kept as logged.
+    Own,
+}
+
+pub struct MinionDefinition {
+    pub name: &'static str,
+    pub species_id: u16,
+    pub attributes: MinionAttributes,
+}
+
+/// Species ids are the ones used by Elite Insights.
+pub const MINIONS: &[MinionDefinition] = &[
+    // Kalla's warband spirits of Renegade
+    MinionDefinition {
+        name: "Jas Razorclaw",
+        species_id: 18791,
+        attributes: MinionAttributes::Owner,
+    },
+    MinionDefinition {
+        name: "Visk Icerazor",
+        species_id: 18524,
+        attributes: MinionAttributes::Owner,
+    },
+    MinionDefinition {
+        name: "Kus Darkrazor",
+        species_id: 18594,
+        attributes: MinionAttributes::Owner,
+    },
+    MinionDefinition {
+        name: "Era Breakrazor",
+        species_id: 18806,
+        attributes: MinionAttributes::Owner,
+    },
+    MinionDefinition {
+        name: "Ofela Soulcleave",
+        species_id: 19002,
+        attributes: MinionAttributes::Owner,
+    },
+];
+
+/// Minions that are not listed use their own attributes.
+pub fn attributes(species_id: Option<u16>) -> MinionAttributes {
+    MINIONS.iter()
+        .find(|x| Some(x.species_id) == species_id)
+        .map(|x| x.attributes)
+        .unwrap_or(MinionAttributes::Own)
+}
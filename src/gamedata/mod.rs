@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::io::BufRead;
+use std::path::Path;
+
+pub mod snapshots;
+pub mod itemstats;
+pub mod runes;
+pub mod procs;
+pub mod sigils;
+pub mod traits;
+pub mod buffs;
+pub mod minions;
+
+pub use snapshots::{GamedataRegistry, GamedataSnapshot, GameRules, ConditionFormula, LifeStealFormula};
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum SkillType {
+    Unknown,
+    Ability,
+    Condition,
+    GenericBuff,
+    Boon,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum BuffStackingType {
+    Intensity,
+    Duration
+}
+
+pub fn get_skill_type(skill_id: u32) -> SkillType {
+    buffs::definition(skill_id).map(|x| x.skill_type).unwrap_or(SkillType::Unknown)
+}
+
+/// None for buffs that are not tracked.
+pub fn get_stack_limit(skill_id: u32) -> Option<u32> {
+    buffs::definition(skill_id).map(|x| x.stack_limit)
+}
+
+/// None for buffs that are not tracked.
+pub fn get_stacking_type(skill_id: u32) -> Option<BuffStackingType> {
+    buffs::definition(skill_id).map(|x| x.stacking)
+}
+
+pub struct SkillData {
+    power_multipliers: HashMap<u32, f64>
+}
+
+impl SkillData {
+    pub fn from_file<P: AsRef<Path>>(filename: P) -> io::Result<Self> {
+        let file = File::open(filename)?;
+        let mut multipliers = HashMap::new();
+        for line in io::BufReader::new(file).lines() {
+            let line = line?;
+            let mut split = line.split_whitespace();
+            let skill_id = split.next().expect("Could not read skill ID");
+            let multiplier = split.next().expect("Could not read skill ID");
+            multipliers.insert(skill_id.parse().expect("Could not parse skill ID"), multiplier.parse().expect("Could not parse skill power multiplier"));
+        }
+        Ok(SkillData {power_multipliers: multipliers})
+    }
+
+    pub fn power_multiplier(&self, skill_id: u32) -> Option<f64> {
+        self.power_multipliers.get(&skill_id).copied()
+    }
+}
\ No newline at end of file
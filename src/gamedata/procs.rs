@@ -0,0 +1,32 @@
+use crate::DamagingCondition;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ProcTrigger {
+    /// Any physical hit on the target
+    Hit,
+    /// Physical hit that is a critical hit
+    Critical,
+    /// Swapping to the weapon set of the sigil
+    WeaponSwap,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ProcEffect {
+    /// Applies stacks of a condition to the target, duration is before condition duration bonuses.
+    Condition { condition: DamagingCondition, stacks: u32, base_duration: u32 },
+    /// Same as Condition, but applied with the next physical hit.
+    ConditionOnNextHit { condition: DamagingCondition, stacks: u32, base_duration: u32 },
+    /// Strikes the target using the weapon strength of the current weapon set, the coefficient is taken from skill data.
+    Strike { skill_id: u32 },
+}
+
+/// An effect that happens when the trigger occurs and the proc is not on cooldown.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProcDefinition {
+    pub trigger: ProcTrigger,
+    /// Chance to proc on each trigger, 1.0 always procs
+    pub chance: f64,
+    /// Internal cooldown in milliseconds
+    pub cooldown: i64,
+    pub effects: &'static [ProcEffect],
+}
@@ -0,0 +1,143 @@
+use crate::{ids, DamagingCondition};
+use crate::build_file::Attributes;
+use crate::gamedata::procs::{ProcDefinition, ProcEffect, ProcTrigger};
+use crate::modifiers::{DamageModifier, DamageTypes, ModifierBucket, ModifierRequirement, ModifierScaling};
+
+/// Effects of a rune set that only apply in some situations.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RuneEffect {
+    Damage(DamageModifier),
+}
+
+/// Bonuses of a full set of six runes, or of five runes of a set with a different sixth one.
+pub struct RuneSet {
+    pub name: &'static str,
+    pub attributes: Attributes,
+    pub condition_duration_all: f64,
+    pub condition_durations: &'static [(DamagingCondition, f64)],
+    pub effects: &'static [RuneEffect],
+    pub procs: &'static [ProcDefinition],
+}
+
+pub const RUNE_SETS: &[RuneSet] = &[
+    RuneSet {
+        name: "Nightmare",
+        attributes: Attributes { condition_damage: 175, ..Attributes::ZERO },
+        condition_duration_all: 0.2,
+        condition_durations: &[],
+        effects: &[],
+        procs: &[],
+    },
+    RuneSet {
+        name: "Tormenting",
+        attributes: Attributes { condition_damage: 175, ..Attributes::ZERO },
+        condition_duration_all: 0.,
+        condition_durations: &[(DamagingCondition::Torment, 0.5)],
+        effects: &[],
+        procs: &[],
+    },
+    // TODO: Values are not verified against a log, keep it out of search spaces until they are
+    RuneSet {
+        name: "Doom",
+        attributes: Attributes { condition_damage: 175, ..Attributes::ZERO },
+        condition_duration_all: 0.,
+        condition_durations: &[(DamagingCondition::Poisoned, 0.25)],
+        effects: &[],
+        procs: &[ProcDefinition {
+            trigger: ProcTrigger::Hit,
+            chance: 1.,
+            cooldown: 10000,
+            effects: &[ProcEffect::Condition { condition: DamagingCondition::Poisoned, stacks: 1, base_duration: 3000 }],
+        }],
+    },
+    RuneSet {
+        name: "Tempest",
+        attributes: Attributes {
+            power: 36,
+            precision: 36,
+            ferocity: 36,
+            condition_damage: 36,
+            expertise: 36,
+            concentration: 36,
+        },
+        condition_duration_all: 0.25,
+        condition_durations: &[],
+        effects: &[],
+        procs: &[],
+    },
+    RuneSet {
+        name: "Trapper",
+        attributes: Attributes { condition_damage: 175, ..Attributes::ZERO },
+        condition_duration_all: 0.15,
+        condition_durations: &[],
+        effects: &[],
+        procs: &[],
+    },
+    // Five Trapper runes, the sixth rune is a Black Diamond one:
+    // 17 cdamage, 17 power, 9 expertise, 9 precision
+    RuneSet {
+        name: "TrapperWithBlackDiamond",
+        attributes: Attributes { condition_damage: 175 + 17, power: 17, expertise: 9, precision: 9, ..Attributes::ZERO },
+        condition_duration_all: 0.15,
+        condition_durations: &[],
+        effects: &[],
+        procs: &[],
+    },
+    // Five Trapper runes, the sixth rune is any rune with 25 cdamage
+    RuneSet {
+        name: "TrapperWith25CondiDamage",
+        attributes: Attributes { condition_damage: 175 + 25, ..Attributes::ZERO },
+        condition_duration_all: 0.15,
+        condition_durations: &[],
+        effects: &[],
+        procs: &[],
+    },
+    // TODO: Might duration bonus is not modelled
+    RuneSet {
+        name: "Strength",
+        attributes: Attributes { power: 175, ..Attributes::ZERO },
+        condition_duration_all: 0.,
+        condition_durations: &[],
+        effects: &[RuneEffect::Damage(DamageModifier {
+            bucket: ModifierBucket::Multiplicative,
+            damage_types: DamageTypes::STRIKE,
+            requirement: ModifierRequirement::PlayerBuff(ids::skills::MIGHT),
+            scaling: ModifierScaling::Flat,
+            value: 0.05,
+        })],
+        procs: &[],
+    },
+    // TODO: Player health is not modelled, the damage bonus above 90% health is assumed to be always active
+    RuneSet {
+        name: "Scholar",
+        attributes: Attributes { power: 175, ferocity: 100, ..Attributes::ZERO },
+        condition_duration_all: 0.,
+        condition_durations: &[],
+        effects: &[RuneEffect::Damage(DamageModifier {
+            bucket: ModifierBucket::Multiplicative,
+            damage_types: DamageTypes::STRIKE,
+            requirement: ModifierRequirement::Always,
+            scaling: ModifierScaling::Flat,
+            value: 0.05,
+        })],
+        procs: &[],
+    },
+    RuneSet {
+        name: "Eagle",
+        attributes: Attributes { precision: 175, ferocity: 100, ..Attributes::ZERO },
+        condition_duration_all: 0.,
+        condition_durations: &[],
+        effects: &[RuneEffect::Damage(DamageModifier {
+            bucket: ModifierBucket::Multiplicative,
+            damage_types: DamageTypes::STRIKE,
+            requirement: ModifierRequirement::TargetHealthBelow(0.5),
+            scaling: ModifierScaling::Flat,
+            value: 0.1,
+        })],
+        procs: &[],
+    },
+];
+
+pub fn by_name(name: &str) -> Option<&'static RuneSet> {
+    RUNE_SETS.iter().find(|x| x.name == name)
+}
@@ -0,0 +1,132 @@
+use crate::{ids, DamagingCondition, Sigil};
+use crate::gamedata::procs::{ProcDefinition, ProcEffect, ProcTrigger};
+use crate::modifiers::{DamageModifier, DamageTypes, ModifierBucket, ModifierRequirement, ModifierScaling};
+
+/// Shared cooldown of all sigils that trigger on weapon swap.
+pub const WEAPON_SWAP_SIGIL_COOLDOWN: i64 = 9000;
+
+/// Effects of a sigil that apply while its weapon set is active.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SigilPassive {
+    /// Extra condition duration, for all conditions if no condition is set.
+    ConditionDuration { condition: Option<DamagingCondition>, value: f64 },
+    Damage(DamageModifier),
+    /// Extra critical chance, 0.07 is +7%
+    CriticalChance(f64),
+}
+
+pub struct SigilDefinition {
+    pub passives: &'static [SigilPassive],
+    pub procs: &'static [ProcDefinition],
+}
+
+const NO_EFFECTS: SigilDefinition = SigilDefinition { passives: &[], procs: &[] };
+
+const EARTH: SigilDefinition = SigilDefinition {
+    passives: &[],
+    procs: &[ProcDefinition {
+        trigger: ProcTrigger::Critical,
+        chance: 1.,
+        cooldown: 2000,
+        effects: &[ProcEffect::Condition { condition: DamagingCondition::Bleeding, stacks: 1, base_duration: 6000 }],
+    }],
+};
+
+const TORMENT: SigilDefinition = SigilDefinition {
+    passives: &[],
+    procs: &[ProcDefinition {
+        trigger: ProcTrigger::Critical,
+        chance: 1.,
+        cooldown: 5000,
+        effects: &[ProcEffect::Condition { condition: DamagingCondition::Torment, stacks: 2, base_duration: 5000 }],
+    }],
+};
+
+const GEOMANCY: SigilDefinition = SigilDefinition {
+    passives: &[],
+    procs: &[ProcDefinition {
+        trigger: ProcTrigger::WeaponSwap,
+        chance: 1.,
+        cooldown: WEAPON_SWAP_SIGIL_COOLDOWN,
+        effects: &[
+            ProcEffect::Strike { skill_id: ids::skills::RING_OF_EARTH },
+            ProcEffect::Condition { condition: DamagingCondition::Bleeding, stacks: 3, base_duration: 8000 },
+        ],
+    }],
+};
+
+const DOOM: SigilDefinition = SigilDefinition {
+    passives: &[],
+    procs: &[ProcDefinition {
+        trigger: ProcTrigger::WeaponSwap,
+        chance: 1.,
+        cooldown: WEAPON_SWAP_SIGIL_COOLDOWN,
+        effects: &[ProcEffect::ConditionOnNextHit { condition: DamagingCondition::Poisoned, stacks: 3, base_duration: 8000 }],
+    }],
+};
+
+const DEMONS: SigilDefinition = SigilDefinition {
+    passives: &[SigilPassive::ConditionDuration { condition: Some(DamagingCondition::Torment), value: 0.2 }],
+    procs: &[],
+};
+
+const AGONY: SigilDefinition = SigilDefinition {
+    passives: &[SigilPassive::ConditionDuration { condition: Some(DamagingCondition::Bleeding), value: 0.2 }],
+    procs: &[],
+};
+
+const SMOLDERING: SigilDefinition = SigilDefinition {
+    passives: &[SigilPassive::ConditionDuration { condition: Some(DamagingCondition::Burning), value: 0.2 }],
+    procs: &[],
+};
+
+const MALICE: SigilDefinition = SigilDefinition {
+    passives: &[SigilPassive::ConditionDuration { condition: None, value: 0.1 }],
+    procs: &[],
+};
+
+const BURSTING: SigilDefinition = SigilDefinition {
+    passives: &[SigilPassive::Damage(DamageModifier {
+        bucket: ModifierBucket::Multiplicative,
+        damage_types: DamageTypes::CONDITIONS,
+        requirement: ModifierRequirement::Always,
+        scaling: ModifierScaling::Flat,
+        value: 0.05,
+    })],
+    procs: &[],
+};
+
+const FORCE: SigilDefinition = SigilDefinition {
+    passives: &[SigilPassive::Damage(DamageModifier {
+        bucket: ModifierBucket::Multiplicative,
+        damage_types: DamageTypes::STRIKE,
+        requirement: ModifierRequirement::Always,
+        scaling: ModifierScaling::Flat,
+        value: 0.05,
+    })],
+    procs: &[],
+};
+
+const ACCURACY: SigilDefinition = SigilDefinition {
+    passives: &[SigilPassive::CriticalChance(0.07)],
+    procs: &[],
+};
+
+pub fn definition(sigil: Sigil) -> &'static SigilDefinition {
+    match sigil {
+        Sigil::None => &NO_EFFECTS,
+        // TODO: Vulnerability duration is not modelled
+        Sigil::Frailty => &NO_EFFECTS,
+        Sigil::Geomancy => &GEOMANCY,
+        Sigil::Earth => &EARTH,
+        Sigil::Doom => &DOOM,
+        Sigil::Torment => &TORMENT,
+        Sigil::Demons => &DEMONS,
+        Sigil::Agony => &AGONY,
+        Sigil::Smoldering => &SMOLDERING,
+        Sigil::Malice => &MALICE,
+        Sigil::Bursting => &BURSTING,
+        Sigil::Force => &FORCE,
+        Sigil::Accuracy => &ACCURACY,
+    }
+}
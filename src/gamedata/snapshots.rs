@@ -0,0 +1,184 @@
+use crate::gamedata::SkillData;
+use crate::evtc::Date;
+use std::io;
+use std::path::Path;
+
+/// Damage of a condition tick: base_damage + condition damage * multiplier.
+#[derive(Copy, Clone, Debug)]
+pub struct ConditionFormula {
+    pub base_damage: f64,
+    pub multiplier: f64,
+}
+
+/// Damage of a life steal: base_damage + power * multiplier.
+#[derive(Copy, Clone, Debug)]
+pub struct LifeStealFormula {
+    pub base_damage: f64,
+    pub multiplier: f64,
+}
+
+/// Game rules that are not part of the skill multiplier files.
+pub struct GameRules {
+    pub base_enemy_armor: u32,
+
+    pub bleeding: ConditionFormula,
+    pub burning: ConditionFormula,
+    pub poisoned: ConditionFormula,
+    pub confusion: ConditionFormula,
+    pub confusion_active: ConditionFormula,
+    pub torment: ConditionFormula,
+    pub torment_moving: ConditionFormula,
+
+    pub battle_scars: LifeStealFormula,
+
+    // This hack is needed because the skill shares the same id for first and later strikes
+    pub searing_fissure_first_strike_multiplier: f64,
+    pub searing_fissure_additional_strike_multiplier: f64,
+    pub searing_fissure_first_strike_burning_duration: u32,
+    pub searing_fissure_additional_strike_burning_duration: u32,
+}
+
+/// Skill data and game rules valid from a specific date until the next snapshot.
+pub struct GamedataSnapshot {
+    pub date: Date,
+    /// First game build the snapshot applies to, as reported by arcdps.
+    pub gw2_build: Option<u64>,
+    pub skills: SkillData,
+    pub rules: GameRules,
+}
+
+struct SnapshotDefinition {
+    date: Date,
+    gw2_build: Option<u64>,
+    multipliers_file: &'static str,
+    rules: fn() -> GameRules,
+}
+
+const SNAPSHOTS: [SnapshotDefinition; 2] = [
+    SnapshotDefinition {
+        date: Date { year: 2020, month: 3, day: 11 },
+        gw2_build: None,
+        multipliers_file: "damage-multipliers-2020.03.11",
+        rules: rules_2020_03_11,
+    },
+    SnapshotDefinition {
+        date: Date { year: 2021, month: 5, day: 11 },
+        gw2_build: Some(115190),
+        multipliers_file: "damage-multipliers-2021.05.11",
+        rules: rules_2021_05_11,
+    },
+];
+
+fn rules_2021_05_11() -> GameRules {
+    // Measured on 2021-03-28.
+    GameRules {
+        base_enemy_armor: 1223,
+        bleeding: ConditionFormula { base_damage: 22.0, multiplier: 0.06 },
+        burning: ConditionFormula { base_damage: 131., multiplier: 0.155 },
+        poisoned: ConditionFormula { base_damage: 33.5, multiplier: 0.06 },
+        confusion: ConditionFormula { base_damage: 10., multiplier: 0. },
+        confusion_active: ConditionFormula { base_damage: 95.5, multiplier: 0.195 },
+        torment: ConditionFormula { base_damage: 31.8, multiplier: 0.09 },
+        torment_moving: ConditionFormula { base_damage: 22., multiplier: 0.06 },
+        battle_scars: LifeStealFormula { base_damage: 298.0, multiplier: 0.1 },
+        searing_fissure_first_strike_multiplier: 0.5,
+        searing_fissure_additional_strike_multiplier: 0.25,
+        searing_fissure_first_strike_burning_duration: 3000,
+        searing_fissure_additional_strike_burning_duration: 1000,
+    }
+}
+
+fn rules_2020_03_11() -> GameRules {
+    // TODO: Not measured, these are the values of 2021.05.11. Only the skill multipliers
+    //       are known to be correct for this snapshot.
+    GameRules {
+        base_enemy_armor: 1223,
+        bleeding: ConditionFormula { base_damage: 22.0, multiplier: 0.06 },
+        burning: ConditionFormula { base_damage: 131., multiplier: 0.155 },
+        poisoned: ConditionFormula { base_damage: 33.5, multiplier: 0.06 },
+        confusion: ConditionFormula { base_damage: 10., multiplier: 0. },
+        confusion_active: ConditionFormula { base_damage: 95.5, multiplier: 0.195 },
+        torment: ConditionFormula { base_damage: 31.8, multiplier: 0.09 },
+        torment_moving: ConditionFormula { base_damage: 22., multiplier: 0.06 },
+        battle_scars: LifeStealFormula { base_damage: 298.0, multiplier: 0.1 },
+        searing_fissure_first_strike_multiplier: 0.5,
+        searing_fissure_additional_strike_multiplier: 0.25,
+        searing_fissure_first_strike_burning_duration: 3000,
+        searing_fissure_additional_strike_burning_duration: 1000,
+    }
+}
+
+/// All known gamedata snapshots, ordered by date.
+pub struct GamedataRegistry {
+    snapshots: Vec<GamedataSnapshot>,
+}
+
+impl GamedataRegistry {
+    /// Loads all snapshots, the multiplier files are read from the given directory.
+    pub fn load<P: AsRef<Path>>(directory: P) -> io::Result<Self> {
+        let mut snapshots = Vec::new();
+        for definition in SNAPSHOTS.iter() {
+            let path = directory.as_ref().join(definition.multipliers_file);
+            snapshots.push(GamedataSnapshot {
+                date: definition.date,
+                gw2_build: definition.gw2_build,
+                skills: SkillData::from_file(path)?,
+                rules: (definition.rules)(),
+            });
+        }
+        snapshots.sort_by_key(|x| x.date);
+
+        Ok(GamedataRegistry { snapshots })
+    }
+
+    pub fn snapshots(&self) -> &[GamedataSnapshot] {
+        &self.snapshots
+    }
+
+    /// The newest snapshot that is not newer than the date.
+    pub fn for_date(&self, date: Date) -> Option<&GamedataSnapshot> {
+        self.snapshots.iter().rev().find(|x| x.date <= date)
+    }
+
+    /// The newest snapshot that is not newer than the game build, snapshots without a known build are skipped.
+    pub fn for_build(&self, gw2_build: u64) -> Option<&GamedataSnapshot> {
+        self.snapshots.iter().rev().find(|x| x.gw2_build.is_some_and(|build| build <= gw2_build))
+    }
+
+    /// Snapshot with the exact date, used to override the automatic choice.
+    pub fn by_date(&self, date: Date) -> Option<&GamedataSnapshot> {
+        self.snapshots.iter().find(|x| x.date == date)
+    }
+
+    pub fn latest(&self) -> &GamedataSnapshot {
+        self.snapshots.last().expect("No gamedata snapshots")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> Date {
+        Date { year, month, day }
+    }
+
+    #[test]
+    fn snapshots_by_date() {
+        let registry = GamedataRegistry::load("gamedata").unwrap();
+        assert_eq!(registry.for_date(date(2021, 5, 11)).unwrap().date, date(2021, 5, 11));
+        assert_eq!(registry.for_date(date(2021, 5, 10)).unwrap().date, date(2020, 3, 11));
+        assert_eq!(registry.for_date(date(2022, 1, 1)).unwrap().date, date(2021, 5, 11));
+        assert_eq!(registry.for_date(date(2020, 3, 11)).unwrap().date, date(2020, 3, 11));
+        assert!(registry.for_date(date(2020, 3, 10)).is_none());
+    }
+
+    #[test]
+    fn snapshots_by_build() {
+        let registry = GamedataRegistry::load("gamedata").unwrap();
+        assert_eq!(registry.for_build(115190).unwrap().date, date(2021, 5, 11));
+        assert_eq!(registry.for_build(120000).unwrap().date, date(2021, 5, 11));
+        // The build of the 2020 snapshot is not known, older builds are chosen by date
+        assert!(registry.for_build(115189).is_none());
+    }
+}
@@ -0,0 +1,127 @@
+use serde::Deserialize;
+use crate::{ids, DamagingCondition, Stat, Trait, WeaponType};
+use crate::modifiers::{DamageModifier, DamageTypes, ModifierBucket, ModifierRequirement, ModifierScaling};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize)]
+pub enum SpecializationLine {
+    Devastation,
+    Invocation,
+    Retribution,
+    Corruption,
+    Salvation,
+    Herald,
+    Renegade,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TraitModifier {
+    /// Extra condition duration, for all conditions if no condition is set.
+    ConditionDuration { condition: Option<DamagingCondition>, value: f64 },
+    /// Gains a part of an attribute as another attribute, computed from gear attributes.
+    StatConversion { from: Stat, to: Stat, ratio: f64 },
+    Damage(DamageModifier),
+}
+
+pub struct TraitDefinition {
+    pub line: SpecializationLine,
+    pub selected_trait: Trait,
+    pub modifiers: &'static [TraitModifier],
+}
+
+pub const TRAITS: &[TraitDefinition] = &[
+    TraitDefinition {
+        line: SpecializationLine::Devastation,
+        selected_trait: Trait::DestructiveImpulses,
+        modifiers: &[
+            TraitModifier::Damage(DamageModifier {
+                bucket: ModifierBucket::Additive,
+                damage_types: DamageTypes::STRIKE_AND_LIFE_STEAL,
+                requirement: ModifierRequirement::WeaponType(WeaponType::DualWield),
+                scaling: ModifierScaling::Flat,
+                value: 0.1,
+            }),
+            TraitModifier::Damage(DamageModifier {
+                bucket: ModifierBucket::Multiplicative,
+                damage_types: DamageTypes::CONDITIONS,
+                requirement: ModifierRequirement::WeaponType(WeaponType::DualWield),
+                scaling: ModifierScaling::Flat,
+                value: 0.1,
+            }),
+            TraitModifier::Damage(DamageModifier {
+                bucket: ModifierBucket::Additive,
+                damage_types: DamageTypes::STRIKE_AND_LIFE_STEAL,
+                requirement: ModifierRequirement::WeaponType(WeaponType::TwoHanded),
+                scaling: ModifierScaling::Flat,
+                value: 0.05,
+            }),
+            TraitModifier::Damage(DamageModifier {
+                bucket: ModifierBucket::Multiplicative,
+                damage_types: DamageTypes::CONDITIONS,
+                requirement: ModifierRequirement::WeaponType(WeaponType::TwoHanded),
+                scaling: ModifierScaling::Flat,
+                value: 0.05,
+            }),
+        ],
+    },
+    TraitDefinition {
+        line: SpecializationLine::Devastation,
+        selected_trait: Trait::TargetedDestruction,
+        modifiers: &[TraitModifier::Damage(DamageModifier {
+            bucket: ModifierBucket::Additive,
+            damage_types: DamageTypes::STRIKE,
+            requirement: ModifierRequirement::Always,
+            scaling: ModifierScaling::PerTargetStack(ids::skills::VULNERABILITY),
+            value: 0.005,
+        })],
+    },
+    TraitDefinition {
+        line: SpecializationLine::Devastation,
+        selected_trait: Trait::UnsuspectingStrikes,
+        modifiers: &[TraitModifier::Damage(DamageModifier {
+            bucket: ModifierBucket::Additive,
+            damage_types: DamageTypes::STRIKE,
+            requirement: ModifierRequirement::TargetHealthAbove(0.8),
+            scaling: ModifierScaling::Flat,
+            value: 0.25,
+        })],
+    },
+    // TODO: Chill is not modelled
+    TraitDefinition {
+        line: SpecializationLine::Corruption,
+        selected_trait: Trait::AbyssalChill,
+        modifiers: &[],
+    },
+    TraitDefinition {
+        line: SpecializationLine::Corruption,
+        selected_trait: Trait::YearningEmpowerment,
+        modifiers: &[TraitModifier::ConditionDuration { condition: None, value: 0.1 }],
+    },
+    TraitDefinition {
+        line: SpecializationLine::Corruption,
+        selected_trait: Trait::AcolyteOfTorment,
+        modifiers: &[TraitModifier::Damage(DamageModifier {
+            bucket: ModifierBucket::Multiplicative,
+            damage_types: DamageTypes::condition(DamagingCondition::Torment),
+            requirement: ModifierRequirement::Always,
+            scaling: ModifierScaling::Flat,
+            value: 0.1,
+        })],
+    },
+    TraitDefinition {
+        line: SpecializationLine::Renegade,
+        selected_trait: Trait::Heartpiercer,
+        modifiers: &[TraitModifier::Damage(DamageModifier {
+            bucket: ModifierBucket::Multiplicative,
+            damage_types: DamageTypes::condition(DamagingCondition::Bleeding),
+            requirement: ModifierRequirement::Always,
+            scaling: ModifierScaling::Flat,
+            value: 0.25,
+        })],
+    },
+];
+
+pub fn definition(selected_trait: Trait) -> &'static TraitDefinition {
+    TRAITS.iter()
+        .find(|x| x.selected_trait == selected_trait)
+        .unwrap_or_else(|| panic!("Trait {:?} is missing from the trait table", selected_trait))
+}
@@ -7,17 +7,35 @@ pub mod skills {
     pub const CHILLED: u32 = 722;
     pub const VULNERABILITY: u32 = 738;
 
+    pub const WEAKNESS: u32 = 742;
+    pub const CRIPPLED: u32 = 721;
+    pub const BLINDED: u32 = 720;
+    pub const IMMOBILE: u32 = 727;
+    pub const SLOW: u32 = 26766;
+    pub const FEAR: u32 = 791;
+    pub const TAUNT: u32 = 27705;
+
     pub const FURY: u32 = 725;
     pub const MIGHT: u32 = 740;
+    pub const QUICKNESS: u32 = 1187;
+    pub const ALACRITY: u32 = 30328;
+    pub const PROTECTION: u32 = 717;
+    pub const REGENERATION: u32 = 718;
+    pub const SWIFTNESS: u32 = 719;
+    pub const VIGOR: u32 = 726;
+    pub const AEGIS: u32 = 743;
+    pub const STABILITY: u32 = 1122;
+    pub const RESISTANCE: u32 = 26980;
+    pub const RESOLUTION: u32 = 873;
 
     pub const KALLAS_FERVOR: u32 = 42883;
     pub const BATTLE_SCARS: u32 = 26646;
 
     pub const SEARING_FISSURE: u32 = 28357;
 
-    /// Superior Rune of Geomancy skill
+    /// Superior Sigil of Geomancy skill
     pub const RING_OF_EARTH: u32 = 9433;
 
-    /// Superior Rune of Doom buff
+    /// Superior Sigil of Doom buff
     pub const DOOM: u32 = 9441;
 }
\ No newline at end of file
@@ -0,0 +1,350 @@
+use std::collections::HashMap;
+use serde::Deserialize;
+use crate::gamedata::{SkillType, get_skill_type};
+use crate::gamedata::procs::ProcDefinition;
+use crate::gamedata::sigils::{self, SigilPassive};
+use crate::gamedata::buffs;
+use crate::modifiers::{DamageContext, DamageModifier, DamageType};
+
+pub mod evtc;
+pub mod ids;
+pub mod gamedata;
+pub mod stats;
+pub mod extract;
+pub mod sim;
+pub mod build_file;
+pub mod equipment;
+pub mod optimize;
+pub mod modifiers;
+pub mod verify;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize)]
+pub enum Stat {
+    Power,
+    Precision,
+    Ferocity,
+    ConditionDamage,
+    Expertise,
+}
+
+#[derive(Eq, PartialEq, Hash, Copy, Clone, Debug, Deserialize)]
+pub enum DamagingCondition {
+    Bleeding,
+    Burning,
+    Confusion,
+    Poisoned,
+    Torment,
+}
+
+impl DamagingCondition {
+    pub const ALL: [DamagingCondition; 5] = [DamagingCondition::Bleeding, DamagingCondition::Burning, DamagingCondition::Confusion, DamagingCondition::Poisoned, DamagingCondition::Torment];
+
+    pub fn from_id(skill_id: u32) -> Self {
+        match skill_id {
+            ids::skills::BLEEDING => DamagingCondition::Bleeding,
+            ids::skills::BURNING => DamagingCondition::Burning,
+            ids::skills::CONFUSION => DamagingCondition::Confusion,
+            ids::skills::POISONED => DamagingCondition::Poisoned,
+            ids::skills::TORMENT => DamagingCondition::Torment,
+            _ => panic!("Unknown condition id!")
+        }
+    }
+    pub fn to_id(&self) -> u32 {
+        match self {
+            DamagingCondition::Bleeding => ids::skills::BLEEDING,
+            DamagingCondition::Burning => ids::skills::BURNING,
+            DamagingCondition::Confusion => ids::skills::CONFUSION,
+            DamagingCondition::Poisoned => ids::skills::POISONED,
+            DamagingCondition::Torment => ids::skills::TORMENT,
+        }
+    }
+}
+
+pub struct StatAmount {
+    pub stat: Stat,
+    pub amount: u32,
+}
+
+#[derive(Debug)]
+pub enum PhysicalHitSource {
+    Unknown,
+    Skill(u32),
+    /// Strike of a sigil proc, attributed to the weapon set that was active.
+    Sigil { sigil: Sigil, skill_id: u32, weapon_set: WeaponSet },
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize)]
+pub enum Trait {
+    DestructiveImpulses,
+    TargetedDestruction,
+    UnsuspectingStrikes,
+    AbyssalChill,
+    YearningEmpowerment,
+    AcolyteOfTorment,
+    Heartpiercer,
+}
+
+#[derive(Debug)]
+pub enum Food {
+    GhostPepperPopper,
+}
+
+#[derive(Debug)]
+pub enum ConditionApplicationSource {
+    Unknown,
+    Skill(u32),
+    /// Sigil proc, attributed to the weapon set that was active.
+    Sigil { sigil: Sigil, weapon_set: WeaponSet },
+    /// Proc of the rune set of the build.
+    Rune,
+    Trait(Trait),
+    Food(Food),
+}
+
+#[derive(Debug)]
+pub enum LifeStealSource {
+    Unknown,
+    Buff(u32),
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum BuffTarget {
+    Player,
+    Target,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize)]
+pub enum WeaponType {
+    DualWield,
+    TwoHanded,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize)]
+pub enum WeaponSet {
+    Land1,
+    Land2,
+}
+
+/// How a physical hit landed on the target.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum HitResult {
+    Normal,
+    Critical,
+    /// Glancing hit of a weakened player, it deals reduced damage and cannot be critical.
+    Glance,
+    /// Blocked, evaded, absorbed or blinded hit, it deals no damage but still triggers on-hit effects.
+    Missed,
+}
+
+#[derive(Debug)]
+pub enum SimulationEvent {
+    /// Player hits the target with a physical attack.
+    PhysicalHit { time: i64, base_damage: u32, coefficient: f64, enemy_armor: u32, source: PhysicalHitSource, result: HitResult, flanking: bool },
+    /// Player applies buff to self.
+    SelfBuffApplication { time: i64, skill_id: u32, base_duration: u32 },
+    /// Player applies buff to target.
+    TargetBuffApplication { time: i64, skill_id: u32, base_duration: u32 },
+    /// Player applies a damaging condition to target.
+    TargetConditionApplication { time: i64, condition: DamagingCondition, base_duration: u32, source: ConditionApplicationSource },
+    /// Condition ticks for damage.
+    ConditionTick { time: i64, target_moving: bool },
+    /// Player damages the enemy with life steal. TODO: Source, if it even can be detected
+    LifeStealHit { time: i64, base_damage: f64, power_scaling: f64, source: LifeStealSource },
+    /// A minion of the player with its own attributes damages the target, the damage does not depend on the build.
+    MinionDamage { time: i64, skill_id: u32, damage: u64 },
+    /// A buff is removed from the player or the target before it expires, for example by a strip or a cleanse.
+    /// Either a single stack or all stacks are removed.
+    BuffRemoval { time: i64, skill_id: u32, target: BuffTarget, all_stacks: bool },
+    /// Player swaps weapons to another weapon set.
+    WeaponSwap { time: i64, weapon_set: WeaponSet },
+}
+
+pub trait BuffUptimes {
+    fn add_stack(&mut self, skill_id: u32, duration: i64, time: i64);
+    fn remove_stack(&mut self, skill_id: u32, time: i64);
+    fn remove_last_stack(&mut self, skill_id: u32, time: i64);
+    fn is_applied(&mut self, skill_id: u32, time: i64) -> bool;
+    fn get_stack_count(&mut self, skill_id: u32, time: i64) -> u32;
+}
+
+#[derive(Default)]
+pub struct LogBuffUptimes {
+    stack_counts: HashMap<u32, u32>
+}
+
+impl LogBuffUptimes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl BuffUptimes for LogBuffUptimes {
+    fn add_stack(&mut self, skill_id: u32, _duration: i64, _time: i64) {
+        *self.stack_counts.entry(skill_id).or_insert(0) += 1;
+    }
+
+    fn remove_stack(&mut self, skill_id: u32, _time: i64) {
+        let count = self.stack_counts.entry(skill_id).or_insert(0);
+        *count = count.saturating_sub(1);
+    }
+
+    fn remove_last_stack(&mut self, skill_id: u32, _time: i64) {
+        *self.stack_counts.entry(skill_id).or_insert(0) = 0;
+    }
+
+    fn is_applied(&mut self, skill_id: u32, _time: i64) -> bool {
+        *self.stack_counts.get(&skill_id).unwrap_or(&0) > 0
+    }
+
+    fn get_stack_count(&mut self, skill_id: u32, _time: i64) -> u32 {
+        *self.stack_counts.get(&skill_id).unwrap_or(&0)
+    }
+}
+
+#[derive(Eq, PartialEq, Hash, Copy, Clone, Debug, Deserialize)]
+pub enum Sigil {
+    None,
+    Frailty,
+    Geomancy,
+    Earth,
+    Doom,
+    Torment,
+    Demons,
+    Agony,
+    Smoldering,
+    Malice,
+    Bursting,
+    Force,
+    Accuracy,
+}
+
+#[derive(Clone)]
+pub struct PlayerStats<TUptimes: BuffUptimes> {
+    pub power: u32,
+    pub precision: u32,
+    pub ferocity: u32,
+    pub condition_damage: u32,
+    pub expertise: u32,
+    pub concentration: u32,
+    /// Critical chance from sources that are not modelled, 0.1 is +10%
+    pub extra_critical_chance: f64,
+    pub set_1_sigils: [Sigil; 2],
+    pub set_2_sigils: [Sigil; 2],
+    pub extra_condition_durations_all: f64,
+    pub extra_condition_durations: HashMap<u32, f64>,
+    pub extra_condition_durations_under_buff: HashMap<u32, (u32, f64)>,
+    /// Damage modifiers of traits, runes and the build file, sigil and buff modifiers are added on top.
+    pub damage_modifiers: Vec<DamageModifier>,
+    /// Procs of the rune set, added by the simulation.
+    pub rune_procs: &'static [ProcDefinition],
+    pub weapon_set: WeaponSet,
+    pub weapon_set_types: [WeaponType; 2],
+    /// Weapon strength used by sigil strikes, main hand for dual wielding.
+    pub weapon_strengths: [f64; 2],
+    pub buff_uptimes: TUptimes,
+}
+
+impl<TUptimes: BuffUptimes> PlayerStats<TUptimes> {
+    pub fn power(&mut self, time: i64) -> u32 {
+        let might = self.buff_uptimes.get_stack_count(ids::skills::MIGHT, time);
+        assert!(might <= gamedata::get_stack_limit(ids::skills::MIGHT).unwrap_or(u32::MAX));
+        self.power + might * stats::MIGHT_POWER
+    }
+    pub fn precision(&mut self, _time: i64) -> u32 {
+        self.precision
+    }
+    pub fn ferocity(&mut self, time: i64) -> u32 {
+        let kallas_fervor = self.buff_uptimes.get_stack_count(ids::skills::KALLAS_FERVOR, time);
+        assert!(kallas_fervor <= gamedata::get_stack_limit(ids::skills::KALLAS_FERVOR).unwrap_or(u32::MAX));
+        self.ferocity + kallas_fervor * stats::KALLAS_FERVOR_FEROCITY
+    }
+    /// Chance of a strike to be critical, between 0 and 1.
+    pub fn critical_chance(&mut self, time: i64) -> f64 {
+        let precision = self.precision(time) as f64 - stats::PRECISION_CRITICAL_CHANCE_OFFSET as f64;
+        let mut chance = precision / stats::PRECISION_PER_CRITICAL_CHANCE_PERCENT / 100. + self.extra_critical_chance;
+        if self.buff_uptimes.is_applied(ids::skills::FURY, time) {
+            chance += stats::FURY_CRITICAL_CHANCE;
+        }
+        for passive in self.current_sigil_passives() {
+            if let SigilPassive::CriticalChance(value) = passive {
+                chance += value;
+            }
+        }
+        chance.clamp(0., 1.)
+    }
+    /// Damage multiplier of critical strikes.
+    pub fn critical_damage(&mut self, time: i64) -> f64 {
+        stats::BASE_CRITICAL_DAMAGE + self.ferocity(time) as f64 / stats::FEROCITY_PER_CRITICAL_DAMAGE
+    }
+    pub fn condition_damage(&mut self, time: i64) -> u32 {
+        let might = self.buff_uptimes.get_stack_count(ids::skills::MIGHT, time);
+        assert!(might <= gamedata::get_stack_limit(ids::skills::MIGHT).unwrap_or(u32::MAX));
+        self.condition_damage + might * stats::MIGHT_CONDITION_DAMAGE
+    }
+    pub fn condition_duration(&mut self, skill_id: u32, time: i64) -> f64 {
+        assert_eq!(get_skill_type(skill_id), SkillType::Condition);
+        let mut duration = 1.
+            + self.expertise as f64 / 1500.
+            + self.extra_condition_durations_all
+            + *self.extra_condition_durations.get(&skill_id).unwrap_or(&0.);
+
+        if let Some((buff, extra_duration)) = self.extra_condition_durations_under_buff.get(&skill_id) {
+            if self.buff_uptimes.is_applied(*buff, time) {
+                duration += extra_duration;
+            }
+        }
+
+        for passive in self.current_sigil_passives() {
+            if let SigilPassive::ConditionDuration { condition, value } = passive {
+                if condition.is_none() || condition.map(|x| x.to_id()) == Some(skill_id) {
+                    duration += value;
+                }
+            }
+        }
+
+        duration.min(2.)
+    }
+    pub fn boon_duration(&mut self, _time: i64) -> f64 {
+        (1. + self.concentration as f64 / 1500.).min(2.)
+    }
+    pub fn damage_mult<TTargetBuffs: BuffUptimes>(&mut self, damage_type: DamageType, time: i64, target_buffs: &mut TTargetBuffs, target_health: f64, flanking: bool) -> f64 {
+        let context = DamageContext { time, weapon_type: self.current_weapon_type(), target_health, flanking };
+        let sigils = *self.current_sigils();
+        let sigil_modifiers = sigils.iter()
+            .flat_map(|&x| sigils::definition(x).passives.iter())
+            .filter_map(|x| match x {
+                SigilPassive::Damage(modifier) => Some(modifier),
+                _ => None,
+            });
+        let modifiers = self.damage_modifiers.iter()
+            .chain(buffs::DAMAGE_MODIFIERS.iter())
+            .chain(sigil_modifiers);
+        modifiers::damage_multiplier(modifiers, damage_type, &context, &mut self.buff_uptimes, target_buffs)
+    }
+
+    pub fn current_sigils(&self) -> &[Sigil; 2] {
+        match self.weapon_set {
+            WeaponSet::Land1 => &self.set_1_sigils,
+            WeaponSet::Land2 => &self.set_2_sigils,
+        }
+    }
+
+    fn current_sigil_passives(&self) -> impl Iterator<Item = SigilPassive> + '_ {
+        self.current_sigils().iter().flat_map(|&x| sigils::definition(x).passives.iter().copied())
+    }
+
+    pub fn current_weapon_type(&self) -> WeaponType {
+        match self.weapon_set {
+            WeaponSet::Land1 => self.weapon_set_types[0],
+            WeaponSet::Land2 => self.weapon_set_types[1],
+        }
+    }
+
+    pub fn current_weapon_strength(&self) -> f64 {
+        match self.weapon_set {
+            WeaponSet::Land1 => self.weapon_strengths[0],
+            WeaponSet::Land2 => self.weapon_strengths[1],
+        }
+    }
+}
+
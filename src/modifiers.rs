@@ -0,0 +1,201 @@
+use crate::{gamedata, BuffUptimes, DamagingCondition, WeaponType};
+
+/// Kind of damage a multiplier is computed for.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DamageType {
+    Strike,
+    Condition(DamagingCondition),
+    LifeSteal,
+}
+
+/// Which conditions a modifier applies to.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ConditionSelection {
+    NoConditions,
+    AllConditions,
+    Only(DamagingCondition),
+}
+
+/// Damage types a modifier applies to.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct DamageTypes {
+    pub strike: bool,
+    pub conditions: ConditionSelection,
+    pub life_steal: bool,
+}
+
+impl DamageTypes {
+    pub const STRIKE: DamageTypes = DamageTypes { strike: true, conditions: ConditionSelection::NoConditions, life_steal: false };
+    pub const CONDITIONS: DamageTypes = DamageTypes { strike: false, conditions: ConditionSelection::AllConditions, life_steal: false };
+    pub const STRIKE_AND_CONDITIONS: DamageTypes = DamageTypes { strike: true, conditions: ConditionSelection::AllConditions, life_steal: false };
+    pub const STRIKE_AND_LIFE_STEAL: DamageTypes = DamageTypes { strike: true, conditions: ConditionSelection::NoConditions, life_steal: true };
+    pub const ALL: DamageTypes = DamageTypes { strike: true, conditions: ConditionSelection::AllConditions, life_steal: true };
+
+    pub const fn condition(condition: DamagingCondition) -> DamageTypes {
+        DamageTypes { strike: false, conditions: ConditionSelection::Only(condition), life_steal: false }
+    }
+
+    pub fn contains(&self, damage_type: DamageType) -> bool {
+        match damage_type {
+            DamageType::Strike => self.strike,
+            DamageType::LifeSteal => self.life_steal,
+            DamageType::Condition(condition) => match self.conditions {
+                ConditionSelection::NoConditions => false,
+                ConditionSelection::AllConditions => true,
+                ConditionSelection::Only(selected) => selected == condition,
+            },
+        }
+    }
+}
+
+/// How a modifier stacks with the others that apply to the same damage.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ModifierBucket {
+    /// Summed with all other additive modifiers, the sum is applied once.
+    Additive,
+    /// Applied as a separate factor.
+    Multiplicative,
+}
+
+/// Situation in which a modifier applies.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ModifierRequirement {
+    Always,
+    /// The player wields the weapon type in the active weapon set.
+    WeaponType(WeaponType),
+    /// The player has the buff.
+    PlayerBuff(u32),
+    /// The target health fraction is at least the threshold.
+    TargetHealthAbove(f64),
+    /// The target health fraction is below the threshold.
+    TargetHealthBelow(f64),
+    /// The player hits the target from the side or the back, only known for strikes.
+    Flanking,
+}
+
+/// How the value of a modifier scales.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ModifierScaling {
+    Flat,
+    /// Multiplied by the stack count of the buff on the player.
+    PerPlayerStack(u32),
+    /// Multiplied by the stack count of the buff on the target.
+    PerTargetStack(u32),
+}
+
+/// A damage modifier from a trait, sigil, rune, buff or target debuff.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DamageModifier {
+    pub bucket: ModifierBucket,
+    pub damage_types: DamageTypes,
+    pub requirement: ModifierRequirement,
+    pub scaling: ModifierScaling,
+    /// Extra damage, 0.1 is +10%
+    pub value: f64,
+}
+
+/// State of the fight needed to check modifier requirements.
+pub struct DamageContext {
+    pub time: i64,
+    pub weapon_type: WeaponType,
+    pub target_health: f64,
+    pub flanking: bool,
+}
+
+/// Combined multiplier of all modifiers that apply to the damage.
+///
+/// Used by both the simulation and the reversal of logged damage, so both directions stay consistent.
+pub fn damage_multiplier<'a, TPlayerBuffs: BuffUptimes, TTargetBuffs: BuffUptimes>(
+    modifiers: impl Iterator<Item = &'a DamageModifier>,
+    damage_type: DamageType,
+    context: &DamageContext,
+    player_buffs: &mut TPlayerBuffs,
+    target_buffs: &mut TTargetBuffs,
+) -> f64 {
+    let mut additive_part = 0.;
+    let mut multiplicative_part = 1.;
+    for modifier in modifiers {
+        if !modifier.damage_types.contains(damage_type) {
+            continue;
+        }
+
+        let applies = match modifier.requirement {
+            ModifierRequirement::Always => true,
+            ModifierRequirement::WeaponType(weapon_type) => context.weapon_type == weapon_type,
+            ModifierRequirement::PlayerBuff(buff) => player_buffs.is_applied(buff, context.time),
+            ModifierRequirement::TargetHealthAbove(threshold) => context.target_health >= threshold,
+            ModifierRequirement::TargetHealthBelow(threshold) => context.target_health < threshold,
+            ModifierRequirement::Flanking => context.flanking,
+        };
+        if !applies {
+            continue;
+        }
+
+        let value = match modifier.scaling {
+            ModifierScaling::Flat => modifier.value,
+            ModifierScaling::PerPlayerStack(buff) => {
+                let stacks = player_buffs.get_stack_count(buff, context.time);
+                assert!(stacks <= gamedata::get_stack_limit(buff).unwrap_or(u32::MAX));
+                stacks as f64 * modifier.value
+            }
+            ModifierScaling::PerTargetStack(buff) => {
+                let stacks = target_buffs.get_stack_count(buff, context.time);
+                assert!(stacks <= gamedata::get_stack_limit(buff).unwrap_or(u32::MAX));
+                stacks as f64 * modifier.value
+            }
+        };
+
+        match modifier.bucket {
+            ModifierBucket::Additive => additive_part += value,
+            ModifierBucket::Multiplicative => multiplicative_part *= 1. + value,
+        }
+    }
+
+    multiplicative_part * (1. + additive_part)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LogBuffUptimes;
+
+    fn modifier(bucket: ModifierBucket, requirement: ModifierRequirement, value: f64) -> DamageModifier {
+        DamageModifier { bucket, damage_types: DamageTypes::STRIKE, requirement, scaling: ModifierScaling::Flat, value }
+    }
+
+    fn strike_multiplier(modifiers: &[DamageModifier], target_health: f64) -> f64 {
+        let context = DamageContext { time: 0, weapon_type: WeaponType::TwoHanded, target_health, flanking: false };
+        damage_multiplier(modifiers.iter(), DamageType::Strike, &context, &mut LogBuffUptimes::new(), &mut LogBuffUptimes::new())
+    }
+
+    #[test]
+    fn additive_modifiers_are_summed_and_multiplicative_ones_compound() {
+        let additive = [
+            modifier(ModifierBucket::Additive, ModifierRequirement::Always, 0.1),
+            modifier(ModifierBucket::Additive, ModifierRequirement::Always, 0.2),
+        ];
+        assert!((strike_multiplier(&additive, 1.) - 1.3).abs() < 1e-9);
+
+        let multiplicative = [
+            modifier(ModifierBucket::Multiplicative, ModifierRequirement::Always, 0.1),
+            modifier(ModifierBucket::Multiplicative, ModifierRequirement::Always, 0.2),
+        ];
+        assert!((strike_multiplier(&multiplicative, 1.) - 1.1 * 1.2).abs() < 1e-9);
+
+        let mixed: Vec<_> = additive.iter().chain(multiplicative.iter()).copied().collect();
+        assert!((strike_multiplier(&mixed, 1.) - 1.1 * 1.2 * 1.3).abs() < 1e-9);
+    }
+
+    #[test]
+    fn target_health_thresholds() {
+        let above = [modifier(ModifierBucket::Additive, ModifierRequirement::TargetHealthAbove(0.5), 0.1)];
+        assert_eq!(strike_multiplier(&above, 0.51), 1.1);
+        assert_eq!(strike_multiplier(&above, 0.5), 1.1);
+        assert_eq!(strike_multiplier(&above, 0.49), 1.);
+
+        let below = [modifier(ModifierBucket::Additive, ModifierRequirement::TargetHealthBelow(0.5), 0.1)];
+        assert_eq!(strike_multiplier(&below, 0.51), 1.);
+        assert_eq!(strike_multiplier(&below, 0.5), 1.);
+        assert_eq!(strike_multiplier(&below, 0.49), 1.1);
+    }
+}
@@ -0,0 +1,176 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use itertools::iproduct;
+use serde::Deserialize;
+use crate::{PlayerStats, Sigil, Stat};
+use crate::build_file::{Attributes, BuildFile};
+use crate::gamedata::runes;
+use crate::sim::SimBuffUptimes;
+
+/// Gear choices that are tried by the optimizer, as stored in a TOML file.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct SearchSpace {
+    /// Infusion slots that are split between the two infusion attributes.
+    pub infusion_slots: u32,
+    /// Attributes of the infusions, expertise and condition damage by default.
+    #[serde(default = "default_infusions")]
+    pub infusions: [Stat; 2],
+    /// Prefixes tried for all armor, trinkets and weapons, the original prefixes are kept if empty.
+    /// The rarities of the original items are kept.
+    #[serde(default)]
+    pub gear: Vec<String>,
+    /// Prefixes tried for the coat, applied after `gear`, the original prefix is kept if empty.
+    #[serde(default)]
+    pub chest: Vec<String>,
+    /// Names of the rune sets
+    pub runes: Vec<String>,
+    /// Sigils tried instead of the original ones, the original sigil is always tried as well.
+    #[serde(default)]
+    pub set_1_sigil_replacements: [Vec<Sigil>; 2],
+    #[serde(default)]
+    pub set_2_sigil_replacements: [Vec<Sigil>; 2],
+}
+
+impl SearchSpace {
+    /// Prefixes are checked against the slots and rarities of the original build.
+    pub fn from_file<P: AsRef<Path>>(filename: P, original: &BuildFile) -> io::Result<Self> {
+        let contents = fs::read_to_string(filename)?;
+        let space: SearchSpace = toml::from_str(&contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if space.infusions[0] == space.infusions[1] {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "The two infusion attributes must be different"));
+        }
+        for (&gear, &chest) in iproduct!(&prefix_options(&space.gear), &prefix_options(&space.chest)) {
+            let mut build = original.clone();
+            replace_prefixes(&mut build, gear, chest);
+            build.validate_gear()?;
+        }
+        if let Some(rune_set) = space.runes.iter().find(|x| runes::by_name(x).is_none()) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Unknown runes {}", rune_set)));
+        }
+        Ok(space)
+    }
+}
+
+pub struct Candidate {
+    pub description: String,
+    pub stats: PlayerStats<SimBuffUptimes>,
+}
+
+fn default_infusions() -> [Stat; 2] {
+    [Stat::Expertise, Stat::ConditionDamage]
+}
+
+fn prefix_options(prefixes: &[String]) -> Vec<Option<&String>> {
+    if prefixes.is_empty() {
+        vec![None]
+    } else {
+        prefixes.iter().map(Some).collect()
+    }
+}
+
+fn replace_prefixes(build: &mut BuildFile, gear: Option<&String>, chest: Option<&String>) {
+    if let Some(prefix) = gear {
+        for item in build.equipment.items_mut() {
+            item.prefix = prefix.clone();
+        }
+        for weapon_set in &mut build.weapon_sets {
+            weapon_set.prefix = prefix.clone();
+        }
+    }
+    if let Some(prefix) = chest {
+        build.equipment.coat.prefix = prefix.clone();
+    }
+}
+
+fn sigil_options(replacements: &[Sigil]) -> Vec<Option<Sigil>> {
+    std::iter::once(None).chain(replacements.iter().copied().map(Some)).collect()
+}
+
+fn replace_sigil(slot: &mut Sigil, replacement: Option<Sigil>) {
+    if let Some(sigil) = replacement {
+        *slot = sigil;
+    }
+}
+
+pub fn candidates(space: &SearchSpace, original: &BuildFile) -> Vec<Candidate> {
+    let mut candidates = Vec::new();
+    let set_1_options = [sigil_options(&space.set_1_sigil_replacements[0]), sigil_options(&space.set_1_sigil_replacements[1])];
+    let set_2_options = [sigil_options(&space.set_2_sigil_replacements[0]), sigil_options(&space.set_2_sigil_replacements[1])];
+
+    let gear_options = prefix_options(&space.gear);
+    let chest_options = prefix_options(&space.chest);
+    let [first_infusion, second_infusion] = space.infusions;
+
+    for (&gear, &chest, first_infusions, runes) in iproduct!(&gear_options, &chest_options, 0..=space.infusion_slots, &space.runes) {
+        let second_infusions = space.infusion_slots - first_infusions;
+        for (&replacement_sigil11, &replacement_sigil12, &replacement_sigil21, &replacement_sigil22)
+        in iproduct!(&set_1_options[0], &set_1_options[1], &set_2_options[0], &set_2_options[1]) {
+            if replacement_sigil11.is_some() && replacement_sigil11 == replacement_sigil12 {
+                continue;
+            }
+            if replacement_sigil21.is_some() && replacement_sigil21 == replacement_sigil22 {
+                continue;
+            }
+            // TODO: Avoid trying both swaps (make an order and start on higher index)
+            let mut build = original.clone();
+
+            // Infusions
+            build.infusions = Attributes::default();
+            *build.infusions.get_mut(first_infusion) = first_infusions;
+            *build.infusions.get_mut(second_infusion) = second_infusions;
+
+            replace_prefixes(&mut build, gear, chest);
+
+            // TODO: Utility enhancement choices
+
+            build.runes = runes.clone();
+
+            let [set_1, set_2] = &mut build.weapon_sets;
+            replace_sigil(&mut set_1.sigils[0], replacement_sigil11);
+            replace_sigil(&mut set_1.sigils[1], replacement_sigil12);
+            replace_sigil(&mut set_2.sigils[0], replacement_sigil21);
+            replace_sigil(&mut set_2.sigils[1], replacement_sigil22);
+            let new_stats = build.to_stats(SimBuffUptimes::new());
+
+            let mut description = format!("Inf: {:?} {} {:?} {} | {} | [{:?};{:?}] [{:?};{:?}]",
+                                          first_infusion, first_infusions, second_infusion, second_infusions,
+                                          runes,
+                                          new_stats.set_1_sigils[0], new_stats.set_1_sigils[1],
+                                          new_stats.set_2_sigils[0], new_stats.set_2_sigils[1],
+            );
+            if let Some(prefix) = gear {
+                description += &format!(" | Gear {}", prefix);
+            }
+            if let Some(prefix) = chest {
+                description += &format!(" | Chest {}", prefix);
+            }
+            candidates.push(Candidate { description, stats: new_stats });
+        }
+    }
+
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gamedata::itemstats::Rarity;
+
+    #[test]
+    fn search_spaces_are_valid_for_the_original_build() {
+        let original = BuildFile::from_file("builds/renegade-original.toml").unwrap();
+        SearchSpace::from_file("builds/renegade-space.toml", &original).unwrap();
+        SearchSpace::from_file("builds/renegade-power-space.toml", &original).unwrap();
+    }
+
+    #[test]
+    fn prefixes_without_known_values_for_the_rarity_are_rejected() {
+        let mut original = BuildFile::from_file("builds/renegade-original.toml").unwrap();
+        // Exotic trinket values are not known
+        original.equipment.amulet.rarity = Rarity::Exotic;
+        assert!(original.validate_gear().is_err());
+        assert!(SearchSpace::from_file("builds/renegade-space.toml", &original).is_err());
+    }
+}
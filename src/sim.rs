@@ -0,0 +1,809 @@
+use itertools::Itertools;
+use std::collections::{BTreeMap, HashMap};
+use std::collections::hash_map::Entry;
+use std::num::NonZeroU32;
+use crate::SimulationEvent::{SelfBuffApplication, TargetConditionApplication, WeaponSwap, TargetBuffApplication, PhysicalHit, ConditionTick, LifeStealHit, MinionDamage, BuffRemoval};
+use crate::{gamedata, stats, BuffUptimes, HitResult, PlayerStats, SimulationEvent, DamagingCondition, PhysicalHitSource, ConditionApplicationSource, Sigil, WeaponSet, LifeStealSource, BuffTarget};
+use crate::gamedata::{SkillType, get_stack_limit, BuffStackingType, GamedataSnapshot};
+use crate::gamedata::procs::{ProcDefinition, ProcEffect, ProcTrigger};
+use crate::gamedata::sigils::{self, WEAPON_SWAP_SIGIL_COOLDOWN};
+use crate::evtc::MaxHealthHistory;
+use crate::modifiers::DamageType;
+use rand::{Rng, SeedableRng};
+use rand::rngs::SmallRng;
+
+#[derive(Clone)]
+struct SimBuffStack {
+    duration: i64,
+}
+
+#[derive(Clone)]
+enum SimBuffState {
+    Duration {
+        last_update_time: i64,
+        active: Option<SimBuffStack>,
+        queued_stacks: Vec<SimBuffStack>,
+        stack_limit: usize,
+    },
+    Intensity {
+        last_update_time: i64,
+        stacks: Vec<SimBuffStack>,
+        stack_limit: usize,
+    },
+}
+
+#[derive(Default, Clone)]
+pub struct SimBuffUptimes {
+    states: HashMap<u32, SimBuffState>
+}
+
+impl SimBuffUptimes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Untracked buffs have no state and are never applied.
+    fn update_state(&mut self, skill_id: u32, time: i64) {
+        let state = match self.states.entry(skill_id) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => match Self::initial_state(skill_id, time) {
+                Some(state) => entry.insert(state),
+                None => return,
+            }
+        };
+        if let SimBuffState::Duration { last_update_time, active, queued_stacks, .. } = state {
+            let mut elapsed_time = time - *last_update_time;
+            assert!(elapsed_time >= 0);
+            while active.is_some() {
+                if elapsed_time <= 0 {
+                    break;
+                }
+
+                let new_active_duration = (active.as_ref().unwrap().duration - elapsed_time).max(0);
+                let time_diff = active.as_ref().unwrap().duration - new_active_duration;
+                let fully_expired = new_active_duration == 0;
+                if fully_expired {
+                    // TODO: Assuming longest stack is used, but it might just be queued
+                    //       and replaced in there - investigate logs
+                    *active = Self::next_queued_stack(queued_stacks);
+                } else {
+                    if let Some(stack) = active {
+                        stack.duration = new_active_duration;
+                    }
+                }
+
+                elapsed_time -= time_diff;
+            }
+            *last_update_time = time;
+        } else if let SimBuffState::Intensity { last_update_time, stacks, .. } = state {
+            let elapsed_time = time - *last_update_time;
+            assert!(elapsed_time >= 0);
+            for stack in &mut *stacks {
+                let new_duration = (stack.duration - elapsed_time).max(0);
+                stack.duration = new_duration;
+            }
+            stacks.retain(|x| x.duration > 0);
+            *last_update_time = time;
+        }
+    }
+
+    fn initial_state(skill_id: u32, time: i64) -> Option<SimBuffState> {
+        let stack_limit = get_stack_limit(skill_id)? as usize;
+        Some(match gamedata::get_stacking_type(skill_id)? {
+            BuffStackingType::Duration => SimBuffState::Duration {
+                last_update_time: time,
+                active: None,
+                queued_stacks: Vec::new(),
+                stack_limit,
+            },
+            BuffStackingType::Intensity => SimBuffState::Intensity {
+                last_update_time: time,
+                stacks: Vec::new(),
+                stack_limit,
+            }
+        })
+    }
+
+    fn next_queued_stack(queued_stacks: &mut Vec<SimBuffStack>) -> Option<SimBuffStack> {
+        queued_stacks.iter()
+            .position_max_by_key(|x| x.duration)
+            .map(|next_stack_index| queued_stacks.swap_remove(next_stack_index))
+    }
+
+    fn current_stack_count(&self, skill_id: u32) -> u32 {
+        let state = self.states.get(&skill_id);
+        if let Some(&SimBuffState::Duration { active, .. }) = state.as_ref() {
+            if active.is_some() { 1 } else { 0 }
+        } else if let Some(SimBuffState::Intensity { stacks, .. }) = state.as_ref() {
+            stacks.len() as u32
+        } else {
+            0
+        }
+    }
+
+    fn insert_stack(&mut self, skill_id: u32, duration: i64) {
+        let state = self.states.get_mut(&skill_id);
+        if let Some(SimBuffState::Duration { queued_stacks, stack_limit, active, .. }) = state {
+            if active.is_none() {
+                *active = Some(SimBuffStack { duration })
+            } else {
+                let free_spots = *stack_limit - (queued_stacks.len() + 1); // + 1 for the active stack
+                if free_spots > 0 {
+                    queued_stacks.push(SimBuffStack { duration });
+                } else {
+                    // TODO: Verify this is correct behavior
+                    // Evict shortest stack
+                    if let Some(shortest_stack_index) = queued_stacks.iter().position_min_by_key(|x| x.duration) {
+                        if duration > queued_stacks[shortest_stack_index].duration {
+                            queued_stacks.swap_remove(shortest_stack_index);
+                            queued_stacks.push(SimBuffStack { duration });
+                        }
+                    }
+                }
+            }
+        } else if let Some(SimBuffState::Intensity { stacks, stack_limit, .. }) = state {
+            let free_spots = *stack_limit - stacks.len();
+            if free_spots > 0 {
+                stacks.push(SimBuffStack { duration });
+            } else {
+                // TODO: Verify this is correct behavior
+                // Evict shortest stack
+                if let Some(shortest_stack_index) = stacks.iter().position_min_by_key(|x| x.duration) {
+                    if duration > stacks[shortest_stack_index].duration {
+                        stacks.swap_remove(shortest_stack_index);
+                        stacks.push(SimBuffStack { duration });
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl BuffUptimes for SimBuffUptimes {
+    fn add_stack(&mut self, skill_id: u32, duration: i64, time: i64) {
+        self.update_state(skill_id, time);
+        self.insert_stack(skill_id, duration);
+    }
+
+    fn remove_stack(&mut self, skill_id: u32, time: i64) {
+        self.update_state(skill_id, time);
+        let state = self.states.get_mut(&skill_id);
+        if let Some(SimBuffState::Duration { active, queued_stacks, .. }) = state {
+            // The active stack is removed, the next one is picked the same way as on expiry
+            *active = Self::next_queued_stack(queued_stacks);
+        } else if let Some(SimBuffState::Intensity { stacks, .. }) = state {
+            // TODO: Verify which stack is removed, assuming the one closest to expiring
+            if let Some(shortest_stack_index) = stacks.iter().position_min_by_key(|x| x.duration) {
+                stacks.swap_remove(shortest_stack_index);
+            }
+        }
+    }
+
+    fn remove_last_stack(&mut self, skill_id: u32, time: i64) {
+        self.update_state(skill_id, time);
+        let state = self.states.get_mut(&skill_id);
+        if let Some(SimBuffState::Duration { active, queued_stacks, .. }) = state {
+            *active = None;
+            queued_stacks.clear();
+        } else if let Some(SimBuffState::Intensity { stacks, .. }) = state {
+            stacks.clear();
+        }
+    }
+
+    fn is_applied(&mut self, skill_id: u32, time: i64) -> bool {
+        self.update_state(skill_id, time);
+        self.current_stack_count(skill_id) > 0
+    }
+
+    fn get_stack_count(&mut self, skill_id: u32, time: i64) -> u32 {
+        self.update_state(skill_id, time);
+        self.current_stack_count(skill_id)
+    }
+}
+
+struct TargetConditions {
+    stacks: HashMap<DamagingCondition, Vec<ConditionStack>>
+}
+
+struct ConditionStack {
+    duration: i64,
+    last_update: i64,
+}
+
+impl TargetConditions {
+    fn new() -> Self {
+        let mut stacks = HashMap::new();
+        for condition in DamagingCondition::ALL {
+            stacks.insert(condition, Vec::new());
+        }
+
+        TargetConditions { stacks }
+    }
+    fn add_condition(&mut self, condition: DamagingCondition, duration: i64, time: i64) {
+        let stacks = self.stacks.get_mut(&condition).unwrap();
+        stacks.push(ConditionStack { duration, last_update: time })
+    }
+}
+
+#[derive(Default)]
+pub struct DamageDistribution {
+    pub damage_by_skill: HashMap<u32, u64>,
+    /// Damage grouped by time and skill, all stacks of a condition tick are summed.
+    pub damage_by_event: BTreeMap<(i64, u32), u64>,
+    total_damage: u64,
+}
+
+impl DamageDistribution {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn add_damage(&mut self, skill_id: u32, damage: u64) {
+        *self.damage_by_skill.entry(skill_id).or_insert(0) += damage;
+        self.total_damage += damage;
+    }
+    pub fn add_damage_at(&mut self, time: i64, skill_id: u32, damage: u64) {
+        *self.damage_by_event.entry((time, skill_id)).or_insert(0) += damage;
+        self.add_damage(skill_id, damage);
+    }
+    pub fn total_damage(&self) -> u64 {
+        self.total_damage
+    }
+}
+
+/// Cooldown and chance state of a proc that is added by the simulation.
+#[derive(Default)]
+struct ProcState {
+    ready_time: i64,
+    accumulated_chance: f64,
+}
+
+impl ProcState {
+    /// Returns true if the proc happens. Chances are rolled with random crits, otherwise they are
+    /// accumulated to keep the simulation deterministic, a 25% proc happens on every fourth trigger
+    /// that is not on cooldown.
+    fn trigger(&mut self, definition: &ProcDefinition, chance: f64, time: i64, crits: &mut Crits) -> bool {
+        if time < self.ready_time {
+            return false;
+        }
+        let procs = match crits.roll(chance) {
+            Some(procs) => procs,
+            None => {
+                self.accumulated_chance += chance;
+                if self.accumulated_chance < 1. {
+                    return false;
+                }
+                self.accumulated_chance -= 1.;
+                true
+            }
+        };
+        if procs {
+            self.ready_time = time + definition.cooldown;
+        }
+        procs
+    }
+}
+
+/// Sigils of the simulated build compared to the original one, per weapon set.
+///
+/// Procs of removed sigils are dropped from the log events, procs of added sigils are simulated.
+#[derive(Default, Debug)]
+pub struct SigilChanges {
+    removed: [Vec<Sigil>; 2],
+    added: [Vec<Sigil>; 2],
+}
+
+impl SigilChanges {
+    pub fn new(original: &[[Sigil; 2]; 2], simulated: &[[Sigil; 2]; 2]) -> Self {
+        let difference = |a: &[Sigil; 2], b: &[Sigil; 2]| a.iter().copied().filter(|x| *x != Sigil::None && !b.contains(x)).collect();
+        SigilChanges {
+            removed: [difference(&original[0], &simulated[0]), difference(&original[1], &simulated[1])],
+            added: [difference(&simulated[0], &original[0]), difference(&simulated[1], &original[1])],
+        }
+    }
+
+    fn set_index(weapon_set: WeaponSet) -> usize {
+        match weapon_set {
+            WeaponSet::Land1 => 0,
+            WeaponSet::Land2 => 1,
+        }
+    }
+
+    fn removed(&self, weapon_set: WeaponSet, sigil: Sigil) -> bool {
+        self.removed[Self::set_index(weapon_set)].contains(&sigil)
+    }
+
+    fn added(&self, weapon_set: WeaponSet) -> &[Sigil] {
+        &self.added[Self::set_index(weapon_set)]
+    }
+}
+
+/// Sigils and rune procs of the build used in the log, their procs are already part of the log events.
+pub struct OriginalProcs {
+    pub sigils: [[Sigil; 2]; 2],
+    pub rune_procs: &'static [ProcDefinition],
+}
+
+/// Procs that are not in the log and have to be simulated, from changed runes and added sigils.
+#[derive(Default)]
+struct SimulatedProcs {
+    rune_procs: &'static [ProcDefinition],
+    rune_states: Vec<ProcState>,
+    sigil_states: HashMap<(Sigil, usize), ProcState>,
+    weapon_swap_ready_time: i64,
+    next_hit_effects: Vec<ProcEffect>,
+}
+
+impl SimulatedProcs {
+    /// Rune procs are only simulated if they differ from the procs of the original runes.
+    fn new(stats: &PlayerStats<SimBuffUptimes>, runes_changed: bool) -> Self {
+        let rune_procs = if runes_changed { stats.rune_procs } else { &[] };
+        SimulatedProcs {
+            rune_procs,
+            rune_states: rune_procs.iter().map(|_| ProcState::default()).collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Effects that happen now, effects for the next hit are kept for later.
+    fn collect_effects(&mut self, definition: &ProcDefinition, effects: &mut Vec<ProcEffect>) {
+        for effect in definition.effects {
+            match *effect {
+                ProcEffect::ConditionOnNextHit { .. } => self.next_hit_effects.push(*effect),
+                _ => effects.push(*effect),
+            }
+        }
+    }
+
+    /// On-crit procs use the critical chance of the hit, which is 0 or 1 unless expected values are used.
+    fn on_hit(&mut self, stats: &PlayerStats<SimBuffUptimes>, changes: &SigilChanges, critical_chance: f64, time: i64, crits: &mut Crits) -> Vec<ProcEffect> {
+        let mut effects = Vec::new();
+        for effect in self.next_hit_effects.drain(..) {
+            if let ProcEffect::ConditionOnNextHit { condition, stacks, base_duration } = effect {
+                effects.push(ProcEffect::Condition { condition, stacks, base_duration });
+            }
+        }
+
+        let trigger_chance = |definition: &ProcDefinition| match definition.trigger {
+            ProcTrigger::Hit => definition.chance,
+            ProcTrigger::Critical => definition.chance * critical_chance,
+            ProcTrigger::WeaponSwap => 0.,
+        };
+        for (i, definition) in self.rune_procs.iter().enumerate() {
+            let chance = trigger_chance(definition);
+            if chance > 0. && self.rune_states[i].trigger(definition, chance, time, crits) {
+                self.collect_effects(definition, &mut effects);
+            }
+        }
+        for &sigil in changes.added(stats.weapon_set) {
+            for (i, definition) in sigils::definition(sigil).procs.iter().enumerate() {
+                let chance = trigger_chance(definition);
+                if chance > 0. && self.sigil_states.entry((sigil, i)).or_default().trigger(definition, chance, time, crits) {
+                    self.collect_effects(definition, &mut effects);
+                }
+            }
+        }
+        effects
+    }
+
+    /// Only one on-swap sigil procs on a swap, sigils from the log take priority over added ones.
+    fn on_weapon_swap(&mut self, stats: &PlayerStats<SimBuffUptimes>, changes: &SigilChanges, time: i64, crits: &mut Crits) -> Vec<ProcEffect> {
+        let mut effects = Vec::new();
+        if time < self.weapon_swap_ready_time {
+            return effects;
+        }
+        let has_swap_proc = |sigil: &Sigil| sigils::definition(*sigil).procs.iter().any(|x| x.trigger == ProcTrigger::WeaponSwap);
+        let added = changes.added(stats.weapon_set);
+        if stats.current_sigils().iter().any(|x| has_swap_proc(x) && !added.contains(x)) {
+            return effects;
+        }
+        for &sigil in added {
+            for (i, definition) in sigils::definition(sigil).procs.iter().enumerate() {
+                if definition.trigger == ProcTrigger::WeaponSwap && self.sigil_states.entry((sigil, i)).or_default().trigger(definition, definition.chance, time, crits) {
+                    self.collect_effects(definition, &mut effects);
+                    self.weapon_swap_ready_time = time + definition.cooldown;
+                    return effects;
+                }
+            }
+        }
+        effects
+    }
+
+    /// An on-swap sigil proc from the log starts the shared cooldown.
+    fn weapon_swap_proc_from_log(&mut self, time: i64) {
+        self.weapon_swap_ready_time = time + WEAPON_SWAP_SIGIL_COOLDOWN;
+    }
+}
+
+/// How the simulation decides which strikes are critical.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CritModel {
+    /// Crits recorded in the log, only valid if precision and fury uptime do not change.
+    /// Simulated strikes never crit.
+    Log,
+    /// Every strike deals its expected damage, on-crit procs accumulate the critical chance.
+    ExpectedValue,
+    /// Crits and simulated procs are rolled from their chance with a seeded generator.
+    Random { seed: u64 },
+}
+
+/// Decides the critical chance of each strike according to the crit model.
+struct Crits {
+    model: CritModel,
+    rng: SmallRng,
+}
+
+impl Crits {
+    fn new(model: CritModel) -> Self {
+        let seed = match model {
+            CritModel::Random { seed } => seed,
+            CritModel::Log | CritModel::ExpectedValue => 0,
+        };
+        Crits { model, rng: SmallRng::seed_from_u64(seed) }
+    }
+
+    /// Returns 0 or 1 unless expected values are used, `logged_critical` is None for simulated strikes.
+    fn critical_chance(&mut self, stats: &mut PlayerStats<SimBuffUptimes>, logged_critical: Option<bool>, time: i64) -> f64 {
+        match self.model {
+            CritModel::Log => if logged_critical == Some(true) { 1. } else { 0. },
+            CritModel::ExpectedValue => stats.critical_chance(time),
+            CritModel::Random { .. } => {
+                let chance = stats.critical_chance(time);
+                if self.rng.gen::<f64>() < chance { 1. } else { 0. }
+            }
+        }
+    }
+
+    /// Rolls a proc chance with random crits, None if chances are accumulated instead.
+    fn roll(&mut self, chance: f64) -> Option<bool> {
+        match self.model {
+            CritModel::Random { .. } => Some(self.rng.gen::<f64>() < chance),
+            CritModel::Log | CritModel::ExpectedValue => None,
+        }
+    }
+}
+
+/// A physical hit before player and target multipliers.
+struct Strike {
+    base_damage: f64,
+    coefficient: f64,
+    enemy_armor: u32,
+    critical_chance: f64,
+    flanking: bool,
+}
+
+fn strike_damage(stats: &mut PlayerStats<SimBuffUptimes>, target_uptimes: &mut SimBuffUptimes, strike: &Strike, time: i64, enemy_health: f64) -> f64 {
+    let mut damage = strike.base_damage * stats.power(time) as f64 * strike.coefficient / strike.enemy_armor as f64;
+    damage *= 1. + strike.critical_chance * (stats.critical_damage(time) - 1.);
+    damage *= stats.damage_mult(DamageType::Strike, time, target_uptimes, enemy_health, strike.flanking);
+    damage
+}
+
+fn get_duration(stats: &mut PlayerStats<SimBuffUptimes>, skill_id: u32, base_duration: u32, time: i64) -> i64 {
+    let duration = match gamedata::get_skill_type(skill_id) {
+        SkillType::Unknown => unreachable!("Unknown buff tracked"),
+        SkillType::Ability => unreachable!("Ability tracked as buff"),
+        SkillType::Condition => (base_duration as f64 * stats.condition_duration(skill_id, time)) as u32,
+        SkillType::Boon => (base_duration as f64 * stats.boon_duration(time)) as u32,
+        SkillType::GenericBuff => base_duration,
+    };
+
+    duration as i64
+}
+
+/// Target state needed to apply simulated proc effects.
+struct ProcTarget<'a> {
+    uptimes: &'a mut SimBuffUptimes,
+    conditions: &'a mut TargetConditions,
+    armor: u32,
+    health: f64,
+}
+
+fn apply_proc_effects(effects: &[ProcEffect],
+                      time: i64,
+                      stats: &mut PlayerStats<SimBuffUptimes>,
+                      target: ProcTarget,
+                      crits: &mut Crits,
+                      gamedata: &GamedataSnapshot,
+                      damage_distribution: &mut DamageDistribution) {
+    for effect in effects {
+        match *effect {
+            ProcEffect::Condition { condition, stacks, base_duration } => {
+                let duration = get_duration(stats, condition.to_id(), base_duration, time);
+                for _ in 0..stacks {
+                    target.conditions.add_condition(condition, duration, time);
+                }
+            }
+            ProcEffect::Strike { skill_id } => {
+                let strike = Strike {
+                    base_damage: stats.current_weapon_strength(),
+                    coefficient: gamedata.skills.power_multiplier(skill_id).expect("Failed to find skill multiplier"),
+                    enemy_armor: target.armor,
+                    critical_chance: crits.critical_chance(stats, None, time),
+                    flanking: false,
+                };
+                let damage = strike_damage(stats, target.uptimes, &strike, time, target.health);
+                damage_distribution.add_damage_at(time, skill_id, damage.round() as u64);
+            }
+            ProcEffect::ConditionOnNextHit { .. } => unreachable!("Next hit effects are applied as conditions"),
+        }
+    }
+}
+
+pub fn sim(mut stats: PlayerStats<SimBuffUptimes>,
+           events: &[SimulationEvent],
+           gamedata: &GamedataSnapshot,
+           enemy_max_health: &MaxHealthHistory,
+           original: &OriginalProcs,
+           crit_model: CritModel,
+) -> DamageDistribution {
+    let mut crits = Crits::new(crit_model);
+    let mut target_uptimes = SimBuffUptimes::new();
+    let mut target_conditions = TargetConditions::new();
+    let sigil_changes = SigilChanges::new(&original.sigils, &[stats.set_1_sigils, stats.set_2_sigils]);
+    let runes_changed = stats.rune_procs != original.rune_procs;
+    let mut simulated_procs = SimulatedProcs::new(&stats, runes_changed);
+    let mut enemy_armor = gamedata.rules.base_enemy_armor;
+    let enemy_health_at = |time: i64, damage_distribution: &DamageDistribution| {
+        let enemy_max_health = enemy_max_health.at(time).unwrap();
+        (enemy_max_health as f64 - damage_distribution.total_damage() as f64) / enemy_max_health as f64
+    };
+
+    let mut damage_distribution = DamageDistribution::new();
+    for event in events {
+        match event {
+            PhysicalHit { time, base_damage, coefficient, source, result, flanking, enemy_armor: hit_enemy_armor } => {
+                if let PhysicalHitSource::Sigil { sigil, weapon_set, .. } = source {
+                    if sigil_changes.removed(*weapon_set, *sigil) {
+                        continue;
+                    }
+                    simulated_procs.weapon_swap_proc_from_log(*time);
+                }
+
+                enemy_armor = *hit_enemy_armor;
+                let enemy_health = enemy_health_at(*time, &damage_distribution);
+                let critical_chance = match result {
+                    HitResult::Normal => crits.critical_chance(&mut stats, Some(false), *time),
+                    HitResult::Critical => crits.critical_chance(&mut stats, Some(true), *time),
+                    HitResult::Glance | HitResult::Missed => 0.,
+                };
+                if *result != HitResult::Missed {
+                    let strike = Strike { base_damage: *base_damage as f64, coefficient: *coefficient, enemy_armor, critical_chance, flanking: *flanking };
+                    let mut damage = strike_damage(&mut stats, &mut target_uptimes, &strike, *time, enemy_health);
+                    if *result == HitResult::Glance {
+                        damage *= stats::GLANCE_DAMAGE_MULTIPLIER;
+                    }
+                    match source {
+                        PhysicalHitSource::Skill(skill_id) | PhysicalHitSource::Sigil { skill_id, .. } => {
+                            damage_distribution.add_damage_at(*time, *skill_id, damage.round() as u64);
+                        }
+                        PhysicalHitSource::Unknown => panic!("Unknown skill for physical damage;"),
+                    }
+                }
+
+                let effects = simulated_procs.on_hit(&stats, &sigil_changes, critical_chance, *time, &mut crits);
+                let target = ProcTarget { uptimes: &mut target_uptimes, conditions: &mut target_conditions, armor: enemy_armor, health: enemy_health };
+                apply_proc_effects(&effects, *time, &mut stats, target, &mut crits, gamedata, &mut damage_distribution);
+            }
+            SelfBuffApplication { time, skill_id, base_duration } => {
+                let duration = get_duration(&mut stats, *skill_id, *base_duration, *time);
+                stats.buff_uptimes.add_stack(*skill_id, duration, *time);
+                //println!("[{}] self buff {}->{}", time, base_duration, duration);
+            }
+            TargetBuffApplication { time, skill_id, base_duration } => {
+                let duration = get_duration(&mut stats, *skill_id, *base_duration, *time);
+                target_uptimes.add_stack(*skill_id, duration, *time);
+                //println!("[{}] target buff {}->{}", time, base_duration, duration);
+            }
+            BuffRemoval { time, skill_id, target, all_stacks } => {
+                let uptimes = match target {
+                    BuffTarget::Player => &mut stats.buff_uptimes,
+                    BuffTarget::Target => &mut target_uptimes,
+                };
+                if *all_stacks {
+                    uptimes.remove_last_stack(*skill_id, *time);
+                } else {
+                    uptimes.remove_stack(*skill_id, *time);
+                }
+            }
+            TargetConditionApplication { time, condition, base_duration, source } => {
+                if let ConditionApplicationSource::Sigil { sigil, weapon_set } = source {
+                    if sigil_changes.removed(*weapon_set, *sigil) {
+                        continue;
+                    }
+                    if *sigil == Sigil::Doom {
+                        simulated_procs.weapon_swap_proc_from_log(*time);
+                    }
+                }
+                if matches!(source, ConditionApplicationSource::Rune) && runes_changed {
+                    continue;
+                }
+
+                let duration = get_duration(&mut stats, condition.to_id(), *base_duration, *time);
+                target_conditions.add_condition(*condition, duration, *time);
+                //println!("[{}] target condi application, duration {}->{}, condi {:?}", time, base_duration, duration, condition)
+            }
+            ConditionTick { time, target_moving } => {
+                let enemy_health = enemy_health_at(*time, &damage_distribution);
+                for (condition, stacks) in target_conditions.stacks.iter_mut() {
+                    let formula = match condition {
+                        DamagingCondition::Bleeding => gamedata.rules.bleeding,
+                        DamagingCondition::Burning => gamedata.rules.burning,
+                        DamagingCondition::Confusion => gamedata.rules.confusion,
+                        DamagingCondition::Poisoned => gamedata.rules.poisoned,
+                        DamagingCondition::Torment if *target_moving => gamedata.rules.torment_moving,
+                        DamagingCondition::Torment => gamedata.rules.torment
+                    };
+
+                    let mut damage = formula.base_damage + stats.condition_damage(*time) as f64 * formula.multiplier;
+                    damage *= stats.damage_mult(DamageType::Condition(*condition), *time, &mut target_uptimes, enemy_health, false);
+
+                    for stack in stacks.iter_mut() {
+                        let elapsed = 1000;
+                        let new_remaining_duration = (stack.duration - elapsed).max(0);
+                        if new_remaining_duration == 0 {
+                            // Partial damage
+
+                            // Round to nearest 1000 / 25 (tick rate)
+                            // https://discord.com/channels/456611641526845473/569588485951062017/737481152482246657
+                            let adjusted_remaining_duration = stack.duration as f64 + (stack.duration as f64 % (1000. / 25.));
+                            let ratio = adjusted_remaining_duration / 1000.;
+                            damage_distribution.add_damage_at(*time, condition.to_id(), (damage * ratio).round() as u64);
+                        } else {
+                            // Full damage
+                            damage_distribution.add_damage_at(*time, condition.to_id(), damage.round() as u64);
+                        }
+                        stack.duration = new_remaining_duration;
+                        stack.last_update = *time;
+                    }
+
+                    stacks.retain(|x| x.duration > 0);
+                }
+                //println!("[{}] condi tick!", time);
+                //println!("      BLEED {} BURN {} TORMENT {} POISON {} CONFUSION {}",
+                //         target_conditions.stacks.get(&DamagingCondition::Bleeding).unwrap().len(),
+                //         target_conditions.stacks.get(&DamagingCondition::Burning).unwrap().len(),
+                //         target_conditions.stacks.get(&DamagingCondition::Torment).unwrap().len(),
+                //         target_conditions.stacks.get(&DamagingCondition::Poisoned).unwrap().len(),
+                //         target_conditions.stacks.get(&DamagingCondition::Confusion).unwrap().len(),
+                //);
+            }
+            LifeStealHit { time, base_damage, power_scaling, source } => {
+                let mut damage = *base_damage + stats.power(*time) as f64 * power_scaling;
+                damage *= stats.damage_mult(DamageType::LifeSteal, *time, &mut target_uptimes, enemy_health_at(*time, &damage_distribution), false);
+                if let LifeStealSource::Buff(buff_id) = source {
+                    damage_distribution.add_damage_at(*time, *buff_id, damage.round() as u64);
+                    // May be a bit off if might share happens at the same time, the order
+                    // is not perfect in that case (life steal from battle scars seems to happen after)
+                    //println!("[{}] life steal {}->{} (scaling {}, pwr {})", time, base_damage, damage, power_scaling, stats.power(*time));
+                } else {
+                    panic!("Unknown source of life steal!")
+                }
+            }
+            MinionDamage { time, skill_id, damage } => {
+                damage_distribution.add_damage_at(*time, *skill_id, *damage);
+            }
+            WeaponSwap { time, weapon_set } => {
+                stats.weapon_set = *weapon_set;
+                //println!("[{}] weaponswap to {:?}", time, weapon_set);
+
+                let effects = simulated_procs.on_weapon_swap(&stats, &sigil_changes, *time, &mut crits);
+                let enemy_health = enemy_health_at(*time, &damage_distribution);
+                let target = ProcTarget { uptimes: &mut target_uptimes, conditions: &mut target_conditions, armor: enemy_armor, health: enemy_health };
+                apply_proc_effects(&effects, *time, &mut stats, target, &mut crits, gamedata, &mut damage_distribution);
+            }
+        }
+    }
+
+    //println!("total damage {}", damage_distribution.total_damage());
+    //for (id, damage) in damage_distribution.damage_by_skill.iter().sorted_by_key(|(id, damage)| *damage).rev() {
+    //    let name = skills.iter().filter(|x| x.id == *id as i32).next().map(|x| x.name.clone()).unwrap_or(String::from("???"));
+    //    println!("{};{};{}", name, id, damage)
+    //}
+    damage_distribution
+}
+
+/// Damage of several simulations with randomly rolled crits.
+pub struct MonteCarloResult {
+    /// Mean damage of each skill over all runs, damage by event is not kept.
+    pub mean: DamageDistribution,
+    /// Standard deviation of the total damage.
+    pub std_dev: f64,
+    pub min: u64,
+    pub max: u64,
+}
+
+/// Runs the simulation with random crits and procs, run i uses the seed `seed + i`.
+pub fn sim_monte_carlo(stats: &PlayerStats<SimBuffUptimes>,
+                       events: &[SimulationEvent],
+                       gamedata: &GamedataSnapshot,
+                       enemy_max_health: &MaxHealthHistory,
+                       original: &OriginalProcs,
+                       seed: u64,
+                       runs: NonZeroU32,
+) -> MonteCarloResult {
+    let runs = runs.get();
+    let results: Vec<_> = (0..runs as u64)
+        .map(|i| sim(stats.clone(), events, gamedata, enemy_max_health, original, CritModel::Random { seed: seed.wrapping_add(i) }))
+        .collect();
+
+    let mut damage_by_skill: HashMap<u32, u64> = HashMap::new();
+    for result in &results {
+        for (&skill_id, &damage) in &result.damage_by_skill {
+            *damage_by_skill.entry(skill_id).or_insert(0) += damage;
+        }
+    }
+    let mut mean = DamageDistribution::new();
+    for (skill_id, damage) in damage_by_skill {
+        mean.add_damage(skill_id, (damage as f64 / runs as f64).round() as u64);
+    }
+
+    let totals: Vec<_> = results.iter().map(|x| x.total_damage()).collect();
+    let mean_total = totals.iter().sum::<u64>() as f64 / runs as f64;
+    let variance = totals.iter().map(|&x| (x as f64 - mean_total).powi(2)).sum::<f64>() / runs as f64;
+
+    MonteCarloResult {
+        mean,
+        std_dev: variance.sqrt(),
+        min: *totals.iter().min().unwrap(),
+        max: *totals.iter().max().unwrap(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ids;
+
+    #[test]
+    fn removing_a_duration_stack_promotes_the_longest_queued_stack() {
+        let mut uptimes = SimBuffUptimes::new();
+        uptimes.add_stack(ids::skills::FURY, 1000, 0);
+        uptimes.add_stack(ids::skills::FURY, 3000, 0);
+        uptimes.add_stack(ids::skills::FURY, 2000, 0);
+
+        // The 3000 stack is active from 500 to 3500, the 2000 stack follows until 5500
+        uptimes.remove_stack(ids::skills::FURY, 500);
+        assert!(uptimes.is_applied(ids::skills::FURY, 3499));
+        assert!(uptimes.is_applied(ids::skills::FURY, 5499));
+        assert!(!uptimes.is_applied(ids::skills::FURY, 5500));
+    }
+
+    #[test]
+    fn removing_the_last_duration_stack_clears_the_queue() {
+        let mut uptimes = SimBuffUptimes::new();
+        uptimes.add_stack(ids::skills::FURY, 1000, 0);
+        uptimes.add_stack(ids::skills::FURY, 3000, 0);
+
+        uptimes.remove_last_stack(ids::skills::FURY, 500);
+        assert!(!uptimes.is_applied(ids::skills::FURY, 500));
+        assert!(!uptimes.is_applied(ids::skills::FURY, 1500));
+    }
+
+    #[test]
+    fn removing_an_intensity_stack_removes_the_shortest_one() {
+        let mut uptimes = SimBuffUptimes::new();
+        uptimes.add_stack(ids::skills::MIGHT, 1000, 0);
+        uptimes.add_stack(ids::skills::MIGHT, 3000, 0);
+        uptimes.add_stack(ids::skills::MIGHT, 2000, 0);
+
+        uptimes.remove_stack(ids::skills::MIGHT, 500);
+        assert_eq!(uptimes.get_stack_count(ids::skills::MIGHT, 900), 2);
+        assert_eq!(uptimes.get_stack_count(ids::skills::MIGHT, 1999), 2);
+        assert_eq!(uptimes.get_stack_count(ids::skills::MIGHT, 2500), 1);
+
+        uptimes.remove_last_stack(ids::skills::MIGHT, 2600);
+        assert_eq!(uptimes.get_stack_count(ids::skills::MIGHT, 2600), 0);
+    }
+
+    #[test]
+    fn untracked_buffs_are_never_applied() {
+        // Not a buff in the gamedata
+        const UNTRACKED: u32 = 1;
+        let mut uptimes = SimBuffUptimes::new();
+        uptimes.add_stack(UNTRACKED, 1000, 0);
+        assert!(!uptimes.is_applied(UNTRACKED, 500));
+        uptimes.remove_stack(UNTRACKED, 600);
+        uptimes.remove_last_stack(UNTRACKED, 700);
+        assert_eq!(uptimes.get_stack_count(UNTRACKED, 800), 0);
+    }
+}
@@ -2,3 +2,10 @@ pub const MIGHT_POWER: u32 = 30;
 pub const MIGHT_CONDITION_DAMAGE: u32 = 30;
 pub const KALLAS_FERVOR_FEROCITY: u32 = 30;
 pub const KALLAS_FERVOR_CONDITION_DAMAGE_MULTIPLIER: f64 = 0.02;
+pub const FURY_CRITICAL_CHANCE: f64 = 0.25;
+pub const GLANCE_DAMAGE_MULTIPLIER: f64 = 0.5;
+/// Critical chance is (precision - 895) / 21 percent.
+pub const PRECISION_CRITICAL_CHANCE_OFFSET: u32 = 895;
+pub const PRECISION_PER_CRITICAL_CHANCE_PERCENT: f64 = 21.;
+pub const BASE_CRITICAL_DAMAGE: f64 = 1.5;
+pub const FEROCITY_PER_CRITICAL_DAMAGE: f64 = 1500.;
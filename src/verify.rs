@@ -0,0 +1,60 @@
+use itertools::Itertools;
+use crate::sim::DamageDistribution;
+
+/// Logged and resimulated damage of the same skill or event.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DamageDifference {
+    pub logged: u64,
+    pub simulated: u64,
+}
+
+impl DamageDifference {
+    /// Resimulated minus logged damage.
+    pub fn error(&self) -> i64 {
+        self.simulated as i64 - self.logged as i64
+    }
+    /// Error relative to the logged damage, None if nothing was logged.
+    pub fn relative_error(&self) -> Option<f64> {
+        if self.logged == 0 {
+            None
+        } else {
+            Some(self.error() as f64 / self.logged as f64)
+        }
+    }
+}
+
+/// Comparison of the log with the resimulation of the build used in the log.
+pub struct Verification {
+    pub total: DamageDifference,
+    /// Damage of each skill and condition, largest absolute error first.
+    pub by_skill: Vec<(u32, DamageDifference)>,
+    /// Damage grouped by time and skill, largest absolute error first.
+    pub worst_events: Vec<((i64, u32), DamageDifference)>,
+}
+
+/// Compares logged damage with resimulated damage, only the `event_count` worst events are kept.
+pub fn compare(logged: &DamageDistribution, simulated: &DamageDistribution, event_count: usize) -> Verification {
+    let total = DamageDifference { logged: logged.total_damage(), simulated: simulated.total_damage() };
+
+    let skills = logged.damage_by_skill.keys().chain(simulated.damage_by_skill.keys()).unique();
+    let by_skill = skills
+        .map(|&skill_id| (skill_id, DamageDifference {
+            logged: *logged.damage_by_skill.get(&skill_id).unwrap_or(&0),
+            simulated: *simulated.damage_by_skill.get(&skill_id).unwrap_or(&0),
+        }))
+        .sorted_by_key(|(skill_id, difference)| (-difference.error().abs(), *skill_id))
+        .collect();
+
+    let events = logged.damage_by_event.keys().chain(simulated.damage_by_event.keys()).unique();
+    let worst_events = events
+        .map(|&key| (key, DamageDifference {
+            logged: *logged.damage_by_event.get(&key).unwrap_or(&0),
+            simulated: *simulated.damage_by_event.get(&key).unwrap_or(&0),
+        }))
+        .filter(|(_, difference)| difference.error() != 0)
+        .sorted_by_key(|(key, difference)| (-difference.error().abs(), *key))
+        .take(event_count)
+        .collect();
+
+    Verification { total, by_skill, worst_events }
+}